@@ -1,4 +1,4 @@
-use tauri::{State, Manager, AppHandle};
+use tauri::{State, Manager, AppHandle, Emitter};
 use std::sync::Mutex;
 use rusqlite::{Connection, Result};
 use serde::{Serialize, Deserialize};
@@ -8,18 +8,85 @@ use std::process::Command;
 use std::os::windows::process::CommandExt;
 use clap::{Parser, Subcommand};
 use tokio::runtime::Runtime;
-use chrono::Local;
+use chrono::{DateTime, Datelike, Local, NaiveTime, Timelike};
+use serde::ser::{Serializer, SerializeStruct};
+
+// CLI status lines. With `--plain` (or NO_EMOJI / NO_COLOR set) emoji become ASCII markers, see `styled`.
+macro_rules! say {
+    ($($arg:tt)*) => { println!("{}", $crate::styled(&format!($($arg)*))) };
+}
+
+macro_rules! say_err {
+    ($($arg:tt)*) => { eprintln!("{}", $crate::styled(&format!($($arg)*))) };
+}
+
+mod server;
+
+// Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Database error: {0}")]
+    Db(String),
+    #[error("Network error: {0}")]
+    Http(String),
+    #[error("Config error: {0}")]
+    Config(String),
+    #[error("Git error: {0}")]
+    Git(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+}
+
+impl AppError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Db(_) => "db",
+            AppError::Http(_) => "http",
+            AppError::Config(_) => "config",
+            AppError::Git(_) => "git",
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self { AppError::Db(e.to_string()) }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self { AppError::Http(e.to_string()) }
+}
+
+// Serialized as { kind, message } so the frontend can branch on the kind
+// and still show the message as-is.
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
 
 // Domain Models
-#[derive(Debug, Serialize, Deserialize)]
+const LOG_TYPES: [&str; 4] = ["task", "note", "problem", "blocked"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogItem {
     id: Option<i64>,
     content: String,
-    log_type: String, // "task", "note", "problem"
+    log_type: String, // "task", "note", "problem", "blocked"
     timestamp: String,
+    #[serde(default)]
+    completed: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitCommit {
     hash: String,
     message: String,
@@ -27,9 +94,116 @@ pub struct GitCommit {
     time: i64,
     repo_name: Option<String>,
     diff: Option<String>,
+    body: Option<String>,
+    // Commits from the same repo left out after this one by `max_commits_per_repo`
+    #[serde(default)]
+    omitted: usize,
+    // Branch (or tag) the commit was reached from; only known when scanning all branches
+    #[serde(default)]
+    branch: Option<String>,
+    // Untruncated diff kept for the stored report when `keep_full_diffs` is on; never sent to the AI
+    #[serde(skip)]
+    full_diff: Option<String>,
+}
+
+// A reusable rule addition ("mention the Q3 migration"); active snippets are
+// appended to `custom_rules` for every review.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptSnippet {
+    pub name: String,
+    pub text: String,
+    #[serde(default)]
+    pub active: bool,
+}
+
+// An alternative AI endpoint (another provider, model or key), compared by `Bench` and usable
+// as a model preset for reviews. An empty key means the main API key.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AiProfile {
+    pub name: String,
+    pub provider: String,
+    pub base_url: Option<String>,
+    pub model: String,
+    pub api_key: String,
+}
+
+impl AiProfile {
+    // "PROVIDER,KEY[,MODEL]", where PROVIDER is a preset name or an OpenAI-compatible base URL.
+    fn parse(name: &str, spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(',').map(str::trim);
+        let (Some(provider), Some(api_key)) = (parts.next(), parts.next()) else {
+            return Err("expected NAME=PROVIDER,KEY[,MODEL]".to_string());
+        };
+        let (provider, base_url) = if provider.starts_with("http") { ("openai", Some(provider.to_string())) } else { (provider, None) };
+        let model = match (parts.next(), provider_preset(provider)) {
+            (Some(model), _) => model.to_string(),
+            (None, Some((_, model))) if base_url.is_none() => model.to_string(),
+            _ => return Err(format!("no default model for '{}', add one after the key", provider)),
+        };
+        Ok(Self { name: name.to_string(), provider: provider.to_string(), base_url, model, api_key: api_key.to_string() })
+    }
+}
+
+impl AppConfig {
+    // `provider_models` entry, else the preset's model.
+    fn default_model(&self, provider: &str) -> Option<String> {
+        self.provider_models.get(provider).cloned().or_else(|| provider_preset(provider).map(|(_, model)| model.to_string()))
+    }
+
+    // The model to request. An empty model, or another provider's default left over from
+    // switching providers (e.g. gpt-4o with deepseek), falls back to this provider's default;
+    // any other model is explicit and always wins. Custom base URLs may serve any model, so
+    // they only get the empty-model fallback.
+    fn effective_model(&self) -> String {
+        let Some(default) = self.default_model(&self.provider) else { return self.model.clone() };
+        let preset_url = provider_preset(&self.provider).map(|(base, _)| base);
+        let custom_url = self.base_url.as_deref().is_some_and(|b| !b.is_empty() && Some(b.trim_end_matches('/')) != preset_url);
+        let mut providers: Vec<&str> = PRESET_PROVIDERS.to_vec();
+        providers.extend(self.provider_models.keys().map(String::as_str));
+        let other_default = !custom_url && self.model != default && providers.iter()
+            .filter(|p| **p != self.provider)
+            .any(|p| self.default_model(p).as_deref() == Some(self.model.as_str()));
+        if self.model.trim().is_empty() || other_default {
+            say_err!("⚠️ Model '{}' doesn't fit provider '{}', using its default '{}'", self.model, self.provider, default);
+            return default;
+        }
+        self.model.clone()
+    }
+
+    // `extra_headers` plus the OpenAI org/project headers when talking to openai; an
+    // explicit extra header of the same name (in any case) wins.
+    fn ai_extra_headers(&self) -> std::collections::HashMap<String, String> {
+        let mut headers = self.extra_headers.clone();
+        if self.provider == "openai" {
+            let ids = [("OpenAI-Organization", &self.openai_org), ("OpenAI-Project", &self.openai_project)];
+            for (name, value) in ids {
+                let overridden = self.extra_headers.keys().any(|k| k.eq_ignore_ascii_case(name));
+                if let Some(value) = value.as_deref().filter(|v| !v.is_empty() && !overridden) {
+                    headers.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+        headers
+    }
+
+    // This config with the endpoint of the named profile swapped in.
+    fn with_profile(&self, name: &str) -> AppResult<AppConfig> {
+        let Some(p) = self.ai_profiles.iter().find(|p| p.name == name) else {
+            let names = self.ai_profiles.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+            return Err(AppError::Config(format!("Unknown model preset '{}' (available: {})", name, if names.is_empty() { "none".to_string() } else { names.join(", ") })));
+        };
+        Ok(AppConfig {
+            provider: p.provider.clone(),
+            base_url: p.base_url.clone(),
+            model: p.model.clone(),
+            api_key: if p.api_key.is_empty() { self.api_key.clone() } else { p.api_key.clone() },
+            ..self.clone()
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct AppConfig {
     pub api_key: String,
     pub git_paths: Vec<String>,
@@ -42,9 +216,159 @@ pub struct AppConfig {
     // Feishu Configuration
     pub feishu_app_id: Option<String>,
     pub feishu_app_secret: Option<String>,
-    pub feishu_target_email: Option<String>,
+    // Recipients; older configs store a single email string
+    #[serde(deserialize_with = "string_or_list")]
+    pub feishu_target_email: Vec<String>,
     pub schedule_time: Option<String>, // Format: "HH:MM"
     pub feishu_enabled: bool,
+    // Only commits made within these hours are scanned (Format: "HH:MM")
+    pub work_hours_start: Option<String>,
+    pub work_hours_end: Option<String>,
+    // Shared secret for the `/trigger-review` webhook; the endpoint is disabled when unset
+    pub webhook_token: Option<String>,
+    // Token-overlap similarity (0.0 - 1.0) above which a log and a commit are
+    // flagged as the same work; 0 disables the check
+    pub dedupe_threshold: f32,
+    // Friendly display names for repos (path -> name); defaults to the directory name
+    pub repo_names: std::collections::HashMap<String, String>,
+    // Ask the AI for a JSON-schema constrained report (summary, tasks, problems)
+    pub structured_output: bool,
+    // Capture the commit message body (%b) in addition to the subject line
+    pub include_commit_body: bool,
+    // Sampling temperature per mode; exports benefit from being more deterministic
+    pub analysis_temperature: f32,
+    pub export_temperature: f32,
+    // Map-reduce review for very large days: summarize commits in batches first
+    pub chunked_review: bool,
+    pub chunk_batch_size: usize,
+    pub prompt_snippets: Vec<PromptSnippet>,
+    // Lead the report with a commit/repo/note count line
+    pub include_metrics: bool,
+    // Additional headers sent with every AI request (org/project ids, proxy tenants...)
+    pub extra_headers: std::collections::HashMap<String, String>,
+    // OpenAI-Organization / OpenAI-Project for accounts with several orgs or projects; only sent to openai
+    pub openai_org: Option<String>,
+    pub openai_project: Option<String>,
+    // Only scan commits by this author; when unset, the repo's `git config user.email` is used
+    pub git_author: Option<String>,
+    pub detect_git_author: bool,
+    // Tasks logged before this time (HH:MM) count as the day's plan in `Review --plan`
+    pub plan_cutoff: String,
+    // Scheduled reports are skipped when both logs and commits number fewer than this
+    pub min_activity_threshold: usize,
+    // Regexes whose matches in logs and commits are replaced with `[REDACTED]` before reaching the AI
+    pub redaction_patterns: Vec<String>,
+    // Keep only the most recent N commits of each repo so one busy project doesn't drown out the rest
+    pub max_commits_per_repo: Option<usize>,
+    // "by_repo" (git log order, repo after repo) or "chronological" (oldest first across all repos)
+    pub commit_order: String,
+    // Alert when the scheduled job fails: POSTs `{"text": ...}` to `alert_webhook_url`, or shows a desktop notification
+    pub alert_on_failure: bool,
+    pub alert_webhook_url: Option<String>,
+    // Desktop notification once the scheduled report has been generated and delivered
+    pub notify_on_success: bool,
+    // Scan every branch instead of just HEAD, tagging each commit with the branch it came from
+    pub scan_all_branches: bool,
+    // How each log is listed in the prompt; supports {n}, {time}, {type} and {content}
+    pub log_line_format: String,
+    // `HookSummary` asks the AI for a one-line summary; when off, commit subjects are logged as-is
+    pub hook_ai_summary: bool,
+    // chrono format for log times in `List` / `Last`, e.g. "%H:%M:%S" or "%I:%M %p"
+    pub time_display_format: String,
+    // When set, rules are read from this file at prompt-build time instead of `custom_rules`
+    pub custom_rules_path: Option<String>,
+    // Deep analysis only fetches diffs when today's commit count is below this; heavy days stay message-only
+    pub auto_deep_threshold: Option<usize>,
+    // How many AI requests chunked reviews may run in parallel
+    pub ai_concurrency: usize,
+    // Store untruncated diffs next to the scheduled report (see `da diff <hash>`)
+    pub keep_full_diffs: bool,
+    // Maps alternate author names to one canonical name in `Contributors`
+    pub author_aliases: std::collections::HashMap<String, String>,
+    // Path appended to `base_url` for chat requests; "/chat/completions" when unset
+    pub completions_path: Option<String>,
+    // User-Agent for every outbound request (AI, Feishu, webhooks); None sends `daily-assistant/<version>`
+    pub user_agent: Option<String>,
+    // Ticket ids (e.g. "[A-Z]+-\d+") found in commit messages group the prompt by ticket
+    pub ticket_regex: Option<String>,
+    // Scheduled reports are skipped before this date (YYYY-MM-DD), e.g. during a vacation
+    pub paused_until: Option<String>,
+    // Days the schedule fires on, 1 = Monday .. 7 = Sunday
+    pub schedule_weekdays: Vec<u8>,
+    // Extra endpoints `Bench` measures alongside the main one; also the model presets
+    pub ai_profiles: Vec<AiProfile>,
+    // Default model per provider, overriding (or adding to) the built-in preset defaults
+    pub provider_models: std::collections::HashMap<String, String>,
+    // Model presets (profile names) used by default for analysis and export; None uses the main model
+    pub analysis_preset: Option<String>,
+    pub export_preset: Option<String>,
+    // Commits further apart than this start a new work session in time estimates
+    pub time_gap_minutes: i64,
+    // Add the commit-based active time estimate to the review prompt
+    pub include_time_estimate: bool,
+    // Rough token limit for the prompt; over it, commit detail is dropped per `truncation_strategy`
+    pub max_prompt_tokens: Option<usize>,
+    // What to drop first when over budget: "oldest_diffs", "all_diffs" or "oldest_commits"
+    pub truncation_strategy: String,
+    // Project to lead the report with (repo display name or `#tag` used in logs); others get summarized briefly
+    pub focus_project: Option<String>,
+    // Longer log contents are truncated with a marker when saved; None keeps everything
+    pub max_log_length: Option<usize>,
+    // Shell commands whose output is added to review prompts, for work that leaves no commits
+    // (e.g. `tail -n 30 ~/.bash_history`); each output is capped at CONTEXT_OUTPUT_LIMIT chars
+    pub context_commands: Vec<String>,
+    // Add each repo's uncommitted changes (`git status`) and stashes to the prompt as work in progress
+    pub include_wip: bool,
+    // Extra attempts for git commands that fail because another git process holds a lock
+    pub git_retries: usize,
+    // Appended to scheduled reports on every sink; supports {date}, {commits}, {logs} and {link}. Empty
+    // (the default) omits it. For example: "—\n📅 {date} · {commits} commits · {logs} notes\n{link}"
+    pub delivery_footer: String,
+    // What {link} in the footer expands to (a URL or file path, may contain {date}); empty when unset
+    pub report_link: Option<String>,
+    // Fixed opener and closer wrapped around delivered reports (outside the AI prompt); support {date}
+    pub report_prefix: String,
+    pub report_suffix: String,
+    // Send reports as an attached .md file instead of a text message, where the sink can
+    pub delivery_as_attachment: bool,
+    // The daemon backs up the database once a day into `backup_dir` ("backups" next to config.json
+    // when unset), keeping the newest `backup_keep` copies
+    pub auto_backup: bool,
+    pub backup_dir: Option<String>,
+    pub backup_keep: usize,
+}
+
+fn default_redaction_patterns() -> Vec<String> {
+    [
+        // Emails
+        r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+        // OpenAI/Stripe-style, GitHub and AWS keys
+        r"\b(sk|pk|rk)-[A-Za-z0-9_-]{16,}",
+        r"\bgh[pousr]_[A-Za-z0-9]{20,}",
+        r"\bAKIA[0-9A-Z]{16}\b",
+        // Bearer tokens and `password=...` style assignments
+        r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{16,}=*",
+        r"(?i)\b(api[_-]?key|secret|token|password|passwd)\s*[:=]\s*\S+",
+    ].iter().map(|p| p.to_string()).collect()
+}
+
+// Accepts null, a single string (possibly comma-separated) or a list of strings.
+fn string_or_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        None => vec![],
+        Some(OneOrMany::One(s)) => split_list(&s),
+        Some(OneOrMany::Many(v)) => v,
+    })
+}
+
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
 }
 
 impl Default for AppConfig {
@@ -94,9 +418,70 @@ impl Default for AppConfig {
             deep_analysis: false,
             feishu_app_id: None,
             feishu_app_secret: None,
-            feishu_target_email: None,
+            feishu_target_email: vec![],
             schedule_time: None,
             feishu_enabled: false,
+            work_hours_start: None,
+            work_hours_end: None,
+            webhook_token: None,
+            dedupe_threshold: 0.0,
+            repo_names: std::collections::HashMap::new(),
+            structured_output: false,
+            include_commit_body: false,
+            analysis_temperature: 0.7,
+            export_temperature: 0.7,
+            chunked_review: false,
+            chunk_batch_size: 20,
+            prompt_snippets: vec![],
+            include_metrics: false,
+            extra_headers: std::collections::HashMap::new(),
+            openai_org: None,
+            openai_project: None,
+            git_author: None,
+            detect_git_author: true,
+            plan_cutoff: "12:00".to_string(),
+            min_activity_threshold: 1,
+            redaction_patterns: default_redaction_patterns(),
+            max_commits_per_repo: None,
+            commit_order: "by_repo".to_string(),
+            alert_on_failure: false,
+            alert_webhook_url: None,
+            notify_on_success: true,
+            scan_all_branches: false,
+            log_line_format: "- {content}".to_string(),
+            hook_ai_summary: true,
+            time_display_format: "%H:%M".to_string(),
+            custom_rules_path: None,
+            auto_deep_threshold: None,
+            ai_concurrency: 1,
+            keep_full_diffs: false,
+            author_aliases: std::collections::HashMap::new(),
+            completions_path: None,
+            user_agent: None,
+            ticket_regex: None,
+            paused_until: None,
+            schedule_weekdays: vec![1, 2, 3, 4, 5, 6, 7],
+            ai_profiles: vec![],
+            provider_models: std::collections::HashMap::new(),
+            analysis_preset: None,
+            export_preset: None,
+            time_gap_minutes: 60,
+            include_time_estimate: false,
+            max_prompt_tokens: None,
+            truncation_strategy: "oldest_diffs".to_string(),
+            focus_project: None,
+            context_commands: vec![],
+            max_log_length: None,
+            include_wip: false,
+            git_retries: 2,
+            delivery_footer: String::new(),
+            report_link: None,
+            report_prefix: String::new(),
+            report_suffix: String::new(),
+            delivery_as_attachment: false,
+            auto_backup: false,
+            backup_dir: None,
+            backup_keep: 7,
         }
     }
 }
@@ -108,288 +493,2585 @@ pub struct DbState {
 
 impl DbState {
     fn init(path: std::path::PathBuf) -> Result<Self> {
-        let conn = Connection::open(path)?;
-        
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS logs (
-                id INTEGER PRIMARY KEY,
-                content TEXT NOT NULL,
-                log_type TEXT NOT NULL,
-                timestamp DATETIME DEFAULT (datetime('now', 'localtime'))
-            )",
-            [],
-        )?;
-        
+        let conn = open_db(path)?;
         Ok(DbState { conn: Mutex::new(conn) })
     }
 }
 
-#[derive(Deserialize)]
-struct AiRequest {
-    provider: String,
-    api_key: String,
-    model: String,
-    prompt: String,
-    base_url: Option<String>,
+// Opens the database and makes sure all tables exist.
+fn open_db(path: std::path::PathBuf) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    // The GUI, daemon and CLI each hold their own connection; wait for a writer instead of failing
+    conn.busy_timeout(std::time::Duration::from_millis(5000))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS logs (
+            id INTEGER PRIMARY KEY,
+            content TEXT NOT NULL,
+            log_type TEXT NOT NULL,
+            timestamp DATETIME DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+    ensure_column(&conn, "logs", "completed", "INTEGER NOT NULL DEFAULT 0")?;
+
+    // One generated report per day, so a retried scheduled job can resend
+    // the stored report instead of calling the AI again.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reports (
+            date TEXT PRIMARY KEY,
+            mode TEXT NOT NULL,
+            content TEXT NOT NULL,
+            delivered INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+    ensure_column(&conn, "reports", "delivered_sinks", "TEXT NOT NULL DEFAULT ''")?;
+    // Full versions of diffs that were truncated in a stored report's prompt
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS report_diffs (
+            date TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            repo TEXT,
+            diff TEXT NOT NULL,
+            PRIMARY KEY (date, hash)
+        )",
+        [],
+    )?;
+    
+    Ok(conn)
 }
 
-// Configuration Helpers
-fn get_config_path() -> std::path::PathBuf {
-    let identifier = "com.tauri-app.daily-assistant";
-    #[cfg(target_os = "windows")]
-    {
-        let app_data = std::env::var("APPDATA").expect("APPDATA not set");
-        let path = std::path::PathBuf::from(app_data).join(identifier);
-        std::fs::create_dir_all(&path).expect("failed to create app data dir");
-        path.join("config.json")
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        let home = std::env::var("HOME").expect("HOME not set");
-        let path = std::path::PathBuf::from(home).join(".config").join(identifier);
-        std::fs::create_dir_all(&path).expect("failed to create app data dir");
-        path.join("config.json")
+// Retries `f` a few times while another connection keeps the database locked for longer
+// than the busy timeout.
+fn retry_if_busy<T>(mut f: impl FnMut() -> AppResult<T>) -> AppResult<T> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(AppError::Db(msg)) if attempt < 3 && (msg.contains("database is locked") || msg.contains("database is busy")) => {
+                say_err!("⚠️ Database is busy, retrying...");
+                std::thread::sleep(std::time::Duration::from_millis(500 * attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
     }
 }
 
-fn load_config() -> AppConfig {
-    let path = get_config_path();
-    if path.exists() {
-        let content = std::fs::read_to_string(path).unwrap_or("{}".to_string());
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        AppConfig::default()
+// Adds a column to an existing table if an older database doesn't have it yet.
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt.query_map([], |row| row.get::<_, String>(1))?.filter_map(|c| c.ok()).any(|c| c == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
     }
+    Ok(())
 }
 
-fn save_config_file(config: &AppConfig) -> Result<(), String> {
-    let path = get_config_path();
-    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    std::fs::write(path, content).map_err(|e| e.to_string())?;
+#[derive(Debug)]
+struct StoredReport {
+    content: String,
+    delivered: bool,
+}
+
+fn load_report(conn: &Connection, date: &str) -> AppResult<Option<StoredReport>> {
+    let mut stmt = conn.prepare("SELECT content, delivered FROM reports WHERE date = ?1")?;
+    let mut rows = stmt.query_map([date], |row| Ok(StoredReport { content: row.get(0)?, delivered: row.get(1)? }))?;
+    Ok(rows.next().transpose()?)
+}
+
+// Names of the schedules.toml entries with a stored report for `date` (stored as "DATE#NAME").
+fn scheduled_report_names(conn: &Connection, date: &str) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT date FROM reports WHERE date LIKE ?1 || '#%' ORDER BY date")?;
+    let keys = stmt.query_map([date], |row| row.get::<_, String>(0))?.collect::<Result<Vec<_>>>()?;
+    Ok(keys.into_iter().filter_map(|k| k.split_once('#').map(|(_, name)| name.to_string())).collect())
+}
+
+fn store_report(conn: &Connection, date: &str, mode: &str, content: &str) -> AppResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO reports (date, mode, content, delivered) VALUES (?1, ?2, ?3, 0)",
+        [date, mode, content],
+    )?;
     Ok(())
 }
 
-// Prompt Generation Logic (Ported from Frontend)
-fn generate_prompt_logic(logs: &[LogItem], commits: &[GitCommit], config: &AppConfig, mode: &str) -> String {
-    let logs_text = logs.iter().map(|l| format!("- {}", l.content)).collect::<Vec<_>>().join("\n");
-    let git_text = commits.iter().map(|g| {
-        let mut text = format!("- [{}] {}", g.repo_name.as_deref().unwrap_or("?"), g.message);
-        if let Some(diff) = &g.diff {
-             text.push_str(&format!("\n  Code Diff Summary:\n```\n{}\n```", diff));
+fn store_report_diffs(conn: &Connection, date: &str, commits: &[GitCommit]) -> AppResult<()> {
+    for c in commits {
+        if let Some(diff) = &c.full_diff {
+            conn.execute(
+                "INSERT OR REPLACE INTO report_diffs (date, hash, repo, diff) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![date, c.hash, c.repo_name, diff],
+            )?;
         }
-        text
-    }).collect::<Vec<_>>().join("\n");
-
-    let base_instruction = if mode == "analysis" {
-        "Provide a comprehensive summary, 3 improvements, and 1 key knowledge point. If code diffs are provided, use them to explain technical details.".to_string()
-    } else { // export
-        format!("Strictly follow the format below:\n\nFormat Template:\n{}", config.report_template)
-    };
+    }
+    Ok(())
+}
 
-    format!(r#"
-        Context:
-        Manual Logs:
-        {}
-        
-        Git Commits:
-        {}
-        
-        System Instruction:
-        {}
-        
-        Additional User Rules:
-        {}
-    "#, logs_text, git_text, base_instruction, config.custom_rules)
+// The most recently stored full diff whose commit hash starts with `hash`.
+fn load_full_diff(conn: &Connection, hash: &str) -> AppResult<Option<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT hash, diff FROM report_diffs WHERE hash LIKE ?1 || '%' ORDER BY date DESC LIMIT 1")?;
+    let mut rows = stmt.query_map([hash], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.next().transpose()?)
 }
 
+// Names of the sinks that already received the report for `date`.
+fn delivered_sinks(conn: &Connection, date: &str) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT delivered_sinks FROM reports WHERE date = ?1")?;
+    let mut rows = stmt.query_map([date], |row| row.get::<_, String>(0))?;
+    let sinks = rows.next().transpose()?.unwrap_or_default();
+    Ok(sinks.split(',').filter(|s| !s.is_empty()).map(str::to_string).collect())
+}
 
-// Tauri Commands
-#[tauri::command]
-fn get_config() -> AppConfig {
-    load_config()
+fn mark_sink_delivered(conn: &Connection, date: &str, sink: &str) -> AppResult<()> {
+    conn.execute(
+        "UPDATE reports SET delivered_sinks = CASE WHEN delivered_sinks = '' THEN ?2 ELSE delivered_sinks || ',' || ?2 END WHERE date = ?1",
+        [date, sink],
+    )?;
+    Ok(())
 }
 
-#[tauri::command]
-fn save_config(config: AppConfig) -> Result<(), String> {
-    save_config_file(&config)
+fn mark_report_delivered(conn: &Connection, date: &str) -> AppResult<()> {
+    conn.execute("UPDATE reports SET delivered = 1 WHERE date = ?1", [date])?;
+    Ok(())
 }
 
+// Cuts content longer than `max_log_length` characters, marking the cut. Returns the original
+// length when it truncated.
+fn limit_log_length(content: &str, config: &AppConfig) -> (String, Option<usize>) {
+    let len = content.chars().count();
+    match config.max_log_length {
+        Some(max) if len > max => (format!("{} …[truncated, {} chars total]", content.chars().take(max).collect::<String>().trim_end(), len), Some(len)),
+        _ => (content.to_string(), None),
+    }
+}
 
-#[tauri::command]
-fn save_log(state: State<DbState>, content: String, log_type: String) -> Result<String, String> {
-    let conn = state.conn.lock().map_err(|_| "Failed to lock db".to_string())?;
+fn insert_log(conn: &Connection, content: &str, log_type: &str) -> AppResult<()> {
     conn.execute(
         "INSERT INTO logs (content, log_type, timestamp) VALUES (?1, ?2, datetime('now', 'localtime'))",
-        [&content, &log_type],
-    ).map_err(|e| e.to_string())?;
-    Ok("Log saved successfully".to_string())
+        [content, log_type],
+    )?;
+    Ok(())
 }
 
-#[tauri::command]
-fn delete_log(state: State<DbState>, id: i64) -> Result<String, String> {
-    let conn = state.conn.lock().map_err(|_| "Failed to lock db".to_string())?;
-    conn.execute("DELETE FROM logs WHERE id = ?1", [&id]).map_err(|e| e.to_string())?;
-    Ok("Log deleted successfully".to_string())
+fn query_today_logs(conn: &Connection) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "WHERE date(timestamp) = date('now', 'localtime') ORDER BY id DESC", [])
 }
 
-#[tauri::command]
-fn get_today_logs(state: State<DbState>) -> Result<Vec<LogItem>, String> {
-    let conn = state.conn.lock().map_err(|_| "Failed to lock db".to_string())?;
-    let mut stmt = conn.prepare(
-        "SELECT id, content, log_type, timestamp FROM logs 
-         WHERE date(timestamp) = date('now', 'localtime')
-         ORDER BY id DESC"
-    ).map_err(|e| e.to_string())?;
-
-    let logs_iter = stmt.query_map([], |row| {
+// Logs stamped from `start` up to (not including) `end`, local time, newest first.
+fn query_logs_in_window(conn: &Connection, start: chrono::NaiveDateTime, end: chrono::NaiveDateTime) -> AppResult<Vec<LogItem>> {
+    let bound = |t: chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    query_logs(conn, "WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY id DESC", [bound(start), bound(end)])
+}
+
+fn query_logs_on(conn: &Connection, date: chrono::NaiveDate) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "WHERE date(timestamp) = ?1 ORDER BY id DESC", [date.format("%Y-%m-%d").to_string()])
+}
+
+// Logs from `from` through `to` (inclusive), oldest first.
+fn query_logs_between(conn: &Connection, from: chrono::NaiveDate, to: chrono::NaiveDate) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "WHERE date(timestamp) BETWEEN ?1 AND ?2 ORDER BY id ASC", [from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string()])
+}
+
+fn query_yesterday_logs(conn: &Connection) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "WHERE date(timestamp) = date('now', 'localtime', '-1 day') ORDER BY id ASC", [])
+}
+
+// Blocked items from the past week that haven't been resolved yet.
+fn query_open_blockers(conn: &Connection) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "WHERE log_type = 'blocked' AND completed = 0 AND date(timestamp) >= date('now', 'localtime', '-7 days') ORDER BY id ASC", [])
+}
+
+const MAX_RECENT_LOGS: i64 = 200;
+
+// Most recent logs across all dates, newest first.
+fn query_recent_logs(conn: &Connection, n: i64) -> AppResult<Vec<LogItem>> {
+    query_logs(conn, "ORDER BY id DESC LIMIT ?1", [n.clamp(1, MAX_RECENT_LOGS)])
+}
+
+const MAX_SEARCH_RESULTS: usize = 100;
+
+// Logs whose content matches `pattern`, newest first. Rows are scanned in pages so a large
+// journal isn't loaded at once, stopping as soon as the result cap is reached.
+fn search_logs_matching(conn: &Connection, pattern: &str) -> AppResult<Vec<LogItem>> {
+    let re = regex::Regex::new(pattern).map_err(|e| AppError::Config(format!("Invalid pattern: {}", e)))?;
+    let mut matches = Vec::new();
+    let mut before = i64::MAX;
+    loop {
+        let page = query_logs(conn, "WHERE id < ?1 ORDER BY id DESC LIMIT 500", [before])?;
+        let Some(last) = page.last() else { break };
+        before = last.id.unwrap_or_default();
+        for log in page.into_iter().filter(|l| re.is_match(&l.content)) {
+            matches.push(log);
+            if matches.len() == MAX_SEARCH_RESULTS { return Ok(matches); }
+        }
+    }
+    Ok(matches)
+}
+
+// Shared SELECT for log rows; `filter` is the WHERE/ORDER/LIMIT tail of the query.
+fn query_logs<P: rusqlite::Params>(conn: &Connection, filter: &str, params: P) -> AppResult<Vec<LogItem>> {
+    let mut stmt = conn.prepare(&format!("SELECT id, content, log_type, timestamp, completed FROM logs {}", filter))?;
+
+    let logs_iter = stmt.query_map(params, |row| {
         Ok(LogItem {
             id: row.get(0)?,
             content: row.get(1)?,
             log_type: row.get(2)?,
             timestamp: row.get(3)?,
+            completed: row.get(4)?,
         })
-    }).map_err(|e| e.to_string())?;
+    })?;
 
     let mut logs = Vec::new();
     for log in logs_iter {
-        logs.push(log.map_err(|e| e.to_string())?);
+        logs.push(log?);
     }
     Ok(logs)
 }
 
-#[tauri::command]
-fn scan_git_repos(paths: Vec<String>, deep_analysis: bool) -> Result<Vec<GitCommit>, String> {
-    let mut all_commits = Vec::new();
+fn set_log_completed(conn: &Connection, id: i64, completed: bool) -> AppResult<()> {
+    if conn.execute("UPDATE logs SET completed = ?1 WHERE id = ?2", rusqlite::params![completed, id])? == 0 {
+        return Err(AppError::NotFound(format!("Log ID {}", id)));
+    }
+    Ok(())
+}
 
-    for path in paths {
-        let repo_name = std::path::Path::new(&path).file_name().and_then(|n| n.to_str()).unwrap_or("Unknown").to_string();
-        
-        let args = &["-C", &path, "log", "--since=midnight", "--pretty=format:%H|%s|%an|%at"];
-        #[cfg(target_os = "windows")]
-        let output = Command::new("git").args(args).output();
-        #[cfg(not(target_os = "windows"))]
-        let output = Command::new("git").args(args).output();
+fn count_logs_older_than(conn: &Connection, days: i64) -> AppResult<usize> {
+    let cutoff = format!("-{} days", days);
+    Ok(conn.query_row("SELECT COUNT(*) FROM logs WHERE timestamp < datetime('now', 'localtime', ?1)", [&cutoff], |r| r.get(0))?)
+}
 
-        if let Ok(out) = output {
-             if out.status.success() {
-                 let stdout = String::from_utf8_lossy(&out.stdout);
-                 for line in stdout.lines() {
-                     let parts: Vec<&str> = line.split('|').collect();
-                     if parts.len() >= 4 {
-                         let hash = parts[0].to_string();
-                         let message = parts[1].to_string();
-                         let mut diff = None;
-                         if deep_analysis {
-                             let diff_args = &["-C", &path, "show", &hash, "--pretty=", "--patch", "--max-count=1"];
-                             #[cfg(target_os = "windows")]
-                             let dout = Command::new("git").args(diff_args).output();
-                             #[cfg(not(target_os = "windows"))]
-                             let dout = Command::new("git").args(diff_args).output();
-                             
-                             if let Ok(d) = dout {
-                                 let raw = String::from_utf8_lossy(&d.stdout).to_string();
-                                 diff = Some(if raw.chars().count() > 3000 { format!("{}... (truncated)", raw.chars().take(3000).collect::<String>()) } else { raw });
-                             }
-                         }
-                         all_commits.push(GitCommit {
-                             hash, message, author: parts[2].to_string(), time: parts[3].parse().unwrap_or(0),
-                             repo_name: Some(repo_name.clone()), diff,
-                         });
-                     }
-                 }
-             }
+// Deletes logs older than `days` days and returns how many were removed.
+fn purge_logs_older_than(conn: &mut Connection, days: i64) -> AppResult<usize> {
+    if days <= 0 {
+        return Err(AppError::Config("Purge age must be at least 1 day".to_string()));
+    }
+    let cutoff = format!("-{} days", days);
+    let tx = conn.transaction()?;
+    let deleted = tx.execute("DELETE FROM logs WHERE timestamp < datetime('now', 'localtime', ?1)", [&cutoff])?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DbStats {
+    total: usize,
+    by_type: Vec<(String, usize)>,
+    earliest: Option<String>,
+    latest: Option<String>,
+    file_size: u64,
+    // Deleted rows aren't kept, but their pages stay in the file until a VACUUM
+    reclaimable_bytes: u64,
+}
+
+fn db_stats_of(conn: &Connection, path: &std::path::Path) -> AppResult<DbStats> {
+    let (total, earliest, latest) = conn.query_row(
+        "SELECT COUNT(*), date(MIN(timestamp)), date(MAX(timestamp)) FROM logs",
+        [],
+        |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    )?;
+    let mut stmt = conn.prepare("SELECT log_type, COUNT(*) FROM logs GROUP BY log_type ORDER BY COUNT(*) DESC")?;
+    let by_type = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.collect::<Result<Vec<_>>>()?;
+    let free_pages: u64 = conn.query_row("PRAGMA freelist_count", [], |r| r.get(0))?;
+    let page_size: u64 = conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+    Ok(DbStats {
+        total,
+        by_type,
+        earliest,
+        latest,
+        file_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        reclaimable_bytes: free_pages * page_size,
+    })
+}
+
+// Every #tag used in a log (lowercased, each counted once per log), most used first.
+// Purely numeric ones are issue references ("fixed #42"), not tags.
+fn tag_counts(conn: &Connection) -> AppResult<Vec<(String, usize)>> {
+    let re = regex::Regex::new(r"(?:^|\s)#([\p{L}\p{N}_-]+)").unwrap();
+    let mut stmt = conn.prepare("SELECT content FROM logs WHERE content LIKE '%#%'")?;
+    let contents = stmt.query_map([], |r| r.get::<_, String>(0))?.collect::<Result<Vec<_>>>()?;
+    let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for content in &contents {
+        let tags: std::collections::HashSet<String> = re.captures_iter(content)
+            .map(|c| c[1].to_lowercase())
+            .filter(|t| !t.chars().all(|c| c.is_ascii_digit()))
+            .collect();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
         }
     }
-    Ok(all_commits)
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(counts)
 }
 
-#[tauri::command]
-async fn call_ai(request: AiRequest) -> Result<String, String> {
-    let client = Client::new();
-    let url = request.base_url.unwrap_or("https://api.openai.com/v1".to_string()) + "/chat/completions";
-    let body = serde_json::json!({
-        "model": request.model,
-        "messages": [{"role": "user", "content": request.prompt}],
-        "temperature": 0.7
-    });
-    
-    let res = client.post(&url)
-        .header("Authorization", format!("Bearer {}", request.api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await.map_err(|e| e.to_string())?;
-        
-    let text = res.text().await.map_err(|e| e.to_string())?;
-    let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
-    if let Some(err) = json.get("error") { return Err(err.to_string()); }
-    Ok(json["choices"][0]["message"]["content"].as_str().unwrap_or(&text).to_string())
+fn get_backup_dir(config: &AppConfig) -> std::path::PathBuf {
+    match &config.backup_dir {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => get_config_path().with_file_name("backups"),
+    }
 }
 
+// Backups in `dir`, oldest first (the timestamped names sort chronologically).
+fn list_backups(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return vec![] };
+    let mut backups: Vec<_> = entries.filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("daily_assistant-") && n.ends_with(".db")))
+        .collect();
+    backups.sort();
+    backups
+}
 
+// Copies the database with SQLite's online backup API, which is safe while the GUI or daemon
+// is writing, to a timestamped file in `dir`, then deletes all but the newest `keep` backups.
+fn backup_db(conn: &Connection, dir: &std::path::Path, keep: usize) -> AppResult<std::path::PathBuf> {
+    std::fs::create_dir_all(dir).map_err(|e| AppError::Config(format!("Can't create {}: {}", dir.display(), e)))?;
+    let path = dir.join(format!("daily_assistant-{}.db", Local::now().format("%Y%m%d-%H%M%S")));
+    conn.backup(rusqlite::MAIN_DB, &path, None)?;
+    let backups = list_backups(dir);
+    for old in backups.iter().take(backups.len().saturating_sub(keep.max(1))) {
+        if let Err(e) = std::fs::remove_file(old) {
+            say_err!("⚠️ Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+    Ok(path)
+}
 
-#[derive(Parser)]
-#[command(name = "Daily Assistant", version, about = "A Daily Review Helper")]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<Commands>,
+// Replaces the database contents with a backup, after checking it is a journal database.
+// Returns the number of logs restored.
+fn restore_db(conn: &mut Connection, from: &std::path::Path) -> AppResult<usize> {
+    if !from.exists() {
+        return Err(AppError::NotFound(format!("Backup file: {}", from.display())));
+    }
+    let source = Connection::open_with_flags(from, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let count = source.query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0))
+        .map_err(|e| AppError::Config(format!("{} is not a journal database: {}", from.display(), e)))?;
+    drop(source);
+    conn.restore(rusqlite::MAIN_DB, from, None::<fn(rusqlite::backup::Progress)>)?;
+    Ok(count)
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Add a new log entry
-    Add { 
-        /// The content of the log/note
-        content: String 
-    },
-    /// List today's logs
-    List,
-    /// Delete a log by ID
-    Del { 
-        /// The ID of the log to delete
-        id: i64 
-    },
-    /// Configure settings (Set --api-key, --add-repo, --deep-analysis)
-    Config {
-        /// Set your OpenAI (or compatible) API Key
-        #[arg(long)]
-        api_key: Option<String>,
-        /// Add a new Git repository path
-        #[arg(long)]
-        add_repo: Option<String>,
-        /// Enable or disable Deep Git Analysis (fetching code diffs)
-        #[arg(long)]
-        deep_analysis: Option<bool>,
-        /// Set Feishu App ID
-        #[arg(long)]
-        feishu_app_id: Option<String>,
-        /// Set Feishu App Secret
-        #[arg(long)]
-        feishu_app_secret: Option<String>,
-        /// Set Feishu Target Email
-        #[arg(long)]
-        feishu_target: Option<String>,
-        /// Set Schedule Time (HH:MM)
-        #[arg(long)]
-        schedule: Option<String>,
-    },
-    /// Sync Git repositories (Use --deep to include diffs)
-    Sync {
-        /// Force a deep sync (fetch code diffs) regardless of config
-        #[arg(long)]
-        deep: bool,
-    },
-    /// Generate AI Review (Default) or Export Report (Use --export)
-    Review {
-        /// Generate a formal report based on template instead of analysis
+#[derive(Debug, Serialize)]
+pub struct MergeReport {
+    inserted: usize,
+    skipped: usize,
+}
+
+// Copies log rows from another journal database, skipping rows whose
+// (content, timestamp) already exist in the current one.
+fn merge_logs_from(conn: &mut Connection, other_path: &str) -> AppResult<MergeReport> {
+    if !std::path::Path::new(other_path).exists() {
+        return Err(AppError::NotFound(format!("Database file: {}", other_path)));
+    }
+    let other = Connection::open(other_path)?;
+    let mut stmt = other.prepare("SELECT content, log_type, timestamp FROM logs ORDER BY id ASC")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+
+    let tx = conn.transaction()?;
+    let mut report = MergeReport { inserted: 0, skipped: 0 };
+    for row in rows {
+        let (content, log_type, timestamp) = row?;
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM logs WHERE content = ?1 AND timestamp = ?2)",
+            [&content, &timestamp],
+            |r| r.get(0),
+        )?;
+        if exists {
+            report.skipped += 1;
+        } else {
+            tx.execute("INSERT INTO logs (content, log_type, timestamp) VALUES (?1, ?2, ?3)", [&content, &log_type, &timestamp])?;
+            report.inserted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+struct AiRequest {
+    provider: String,
+    api_key: String,
+    model: String,
+    prompt: String,
+    base_url: Option<String>,
+    #[serde(default)]
+    response_format: Option<serde_json::Value>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    extra_headers: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    completions_path: Option<String>,
+    #[serde(default)]
+    user_agent: Option<String>,
+}
+
+const PRESET_PROVIDERS: [&str; 3] = ["openai", "deepseek", "moonshot"];
+
+// Default (base URL, model) for OpenAI-compatible providers that live elsewhere.
+fn provider_preset(provider: &str) -> Option<(&'static str, &'static str)> {
+    match provider {
+        "openai" => Some(("https://api.openai.com/v1", "gpt-4o")),
+        "deepseek" => Some(("https://api.deepseek.com/v1", "deepseek-chat")),
+        "moonshot" => Some(("https://api.moonshot.cn/v1", "moonshot-v1-8k")),
+        _ => None,
+    }
+}
+
+// `base_url` (or the provider's default) + `completions_path`, for OpenAI-compatible
+// gateways that mount the API elsewhere.
+fn completions_url(provider: &str, base_url: Option<&str>, path: Option<&str>) -> String {
+    let base = base_url.filter(|b| !b.is_empty())
+        .or(provider_preset(provider).map(|(base, _)| base))
+        .unwrap_or("https://api.openai.com/v1");
+    format!("{}{}", base.trim_end_matches('/'), path.unwrap_or("/chat/completions"))
+}
+
+// `file:PATH` secrets are read from that file (Docker secrets, password manager mounts) each time
+// they're used, with surrounding whitespace trimmed; any other value is the secret itself.
+fn resolve_secret(value: &str) -> AppResult<String> {
+    let Some(path) = value.strip_prefix("file:") else { return Ok(value.to_string()) };
+    std::fs::read_to_string(path.trim())
+        .map(|s| s.trim().to_string())
+        .map_err(|e| AppError::Config(format!("Can't read secret file {}: {}", path.trim(), e)))
+}
+
+// Default AI request headers plus `extra_headers`. Extra headers are inserted
+// last, so Authorization/Content-Type only change if set there explicitly.
+fn ai_headers(api_key: &str, extra: &std::collections::HashMap<String, String>) -> AppResult<reqwest::header::HeaderMap> {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    let invalid = |name: &str, e: &dyn std::fmt::Display| AppError::Config(format!("Invalid header {}: {}", name, e));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Authorization", HeaderValue::from_str(&format!("Bearer {}", resolve_secret(api_key)?)).map_err(|e| invalid("Authorization", &e))?);
+    headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+    for (name, value) in extra {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(name, &e))?;
+        headers.insert(header_name, HeaderValue::from_str(value).map_err(|e| invalid(name, &e))?);
+    }
+    Ok(headers)
+}
+
+fn build_ai_request(config: &AppConfig, prompt: String, mode: &str) -> AiRequest {
+    let preset = if mode == "export" { &config.export_preset } else { &config.analysis_preset };
+    let config = &match preset.as_deref().map(|name| config.with_profile(name)) {
+        Some(Ok(c)) => c,
+        Some(Err(e)) => {
+            say_err!("⚠️ {}, using the main model", e);
+            config.clone()
+        }
+        None => config.clone(),
+    };
+    AiRequest {
+        provider: config.provider.clone(),
+        api_key: config.api_key.clone(),
+        model: config.effective_model(),
+        base_url: config.base_url.clone(),
+        prompt,
+        response_format: (config.structured_output && mode != "summary").then(structured_report_format),
+        temperature: Some(if mode == "export" { config.export_temperature } else { config.analysis_temperature }),
+        extra_headers: config.ai_extra_headers(),
+        completions_path: config.completions_path.clone(),
+        user_agent: config.user_agent.clone(),
+    }
+}
+
+// Report returned by the AI when `structured_output` is enabled, so the app
+// can render sections itself instead of parsing prose.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StructuredReport {
+    summary: String,
+    tasks: Vec<String>,
+    problems: Vec<String>,
+}
+
+impl StructuredReport {
+    fn to_markdown(&self) -> String {
+        let list = |items: &[String]| if items.is_empty() { "- (none)".to_string() } else { items.iter().map(|i| format!("- {}", i)).collect::<Vec<_>>().join("\n") };
+        format!("**Summary**\n{}\n\n**Tasks**\n{}\n\n**Problems**\n{}", self.summary, list(&self.tasks), list(&self.problems))
+    }
+}
+
+// OpenAI `response_format` constraining the reply to a StructuredReport.
+fn structured_report_format() -> serde_json::Value {
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": "daily_report",
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string" },
+                    "tasks": { "type": "array", "items": { "type": "string" } },
+                    "problems": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["summary", "tasks", "problems"],
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+fn parse_structured_report(raw: &str) -> AppResult<StructuredReport> {
+    serde_json::from_str(raw).map_err(|e| AppError::Http(format!("Invalid structured report: {}", e)))
+}
+
+// Turns the raw AI reply into display text, rendering structured replies as markdown.
+// Summaries are cut down to their first line in case the AI added more.
+fn render_ai_reply(raw: String, config: &AppConfig, mode: &str) -> AppResult<String> {
+    if mode == "summary" {
+        Ok(raw.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default().to_string())
+    } else if config.structured_output {
+        Ok(parse_structured_report(&raw)?.to_markdown())
+    } else {
+        Ok(raw)
+    }
+}
+
+// Configuration Helpers
+fn get_config_path() -> std::path::PathBuf {
+    let identifier = "com.tauri-app.daily-assistant";
+    #[cfg(target_os = "windows")]
+    {
+        let app_data = std::env::var("APPDATA").expect("APPDATA not set");
+        let path = std::path::PathBuf::from(app_data).join(identifier);
+        std::fs::create_dir_all(&path).expect("failed to create app data dir");
+        path.join("config.json")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = std::env::var("HOME").expect("HOME not set");
+        let path = std::path::PathBuf::from(home).join(".config").join(identifier);
+        std::fs::create_dir_all(&path).expect("failed to create app data dir");
+        path.join("config.json")
+    }
+}
+
+fn load_config() -> AppConfig {
+    let path = get_config_path();
+    if path.exists() {
+        let content = std::fs::read_to_string(path).unwrap_or("{}".to_string());
+        serde_json::from_str(&content).unwrap_or_default()
+    } else {
+        AppConfig::default()
+    }
+}
+
+// Opens $VISUAL / $EDITOR (Notepad on Windows, vi elsewhere) on a scratch file, like `git commit`
+// does, and returns the saved text without '#' comment lines.
+fn edit_in_editor() -> std::io::Result<String> {
+    let (path, mut file) = create_note_file()?;
+    std::io::Write::write_all(&mut file, b"\n# Write your note above. Lines starting with '#' are ignored; an empty note is discarded.\n")?;
+    drop(file);
+
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(target_os = "windows") { "notepad".to_string() } else { "vi".to_string() });
+    // Run through the shell like git does, so "code --wait" and quoted paths with spaces both work
+    #[cfg(target_os = "windows")]
+    let status = Command::new("cmd").raw_arg(format!("/S /C \"{} \"{}\"\"", editor, path.display())).status();
+    #[cfg(not(target_os = "windows"))]
+    let status = Command::new("sh").arg("-c").arg(format!("{} \"$@\"", editor)).arg(&editor).arg(&path).status();
+
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    let status = status?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("{} exited with {}", editor, status)));
+    }
+    Ok(text?.lines().filter(|l| !l.starts_with('#')).collect::<Vec<_>>().join("\n").trim().to_string())
+}
+
+// A fresh, owner-only temp file for `edit_in_editor`. The name isn't guessable from the pid alone,
+// and `create_new` refuses to follow a file or symlink someone else planted at that path.
+fn create_note_file() -> std::io::Result<(std::path::PathBuf, std::fs::File)> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut attempts: u32 = 0;
+    loop {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let path = std::env::temp_dir().join(format!("da-note-{}-{:08x}.md", std::process::id(), nanos ^ attempts.wrapping_mul(0x9e37_79b9)));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempts < 16 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Formats a stored timestamp. Rows are normally SQLite's "YYYY-MM-DD HH:MM:SS", but merged or
+// hand-edited databases may hold ISO-8601 or minute-precision values; anything else is shown
+// as-is rather than sliced, since short or odd strings used to panic `List`.
+fn format_timestamp(ts: &str, format: &str) -> String {
+    use std::fmt::Write;
+    let parsed = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"].iter()
+        .find_map(|f| chrono::NaiveDateTime::parse_from_str(ts.trim(), f).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(ts.trim()).ok().map(|dt| dt.with_timezone(&Local).naive_local()));
+    let Some(dt) = parsed else { return ts.to_string(); };
+    let mut out = String::new();
+    // An invalid format string makes chrono's Display fail rather than print garbage
+    if write!(out, "{}", dt.format(format)).is_err() { return ts.to_string(); }
+    out
+}
+
+// Keeps the first few characters so you can tell which key is in use.
+fn mask_secret(secret: &str) -> String {
+    if secret.is_empty() { return String::new(); }
+    // A file reference isn't the secret itself, and is more useful unmasked
+    if secret.starts_with("file:") { return secret.to_string(); }
+    let visible: String = secret.chars().take(4).collect();
+    format!("{}****", visible)
+}
+
+// A copy of the config that is safe to print or share.
+fn masked_config(config: &AppConfig) -> AppConfig {
+    let mut masked = config.clone();
+    masked.api_key = mask_secret(&config.api_key);
+    masked.feishu_app_secret = config.feishu_app_secret.as_deref().map(mask_secret);
+    masked.webhook_token = config.webhook_token.as_deref().map(mask_secret);
+    // Slack/Feishu-style incoming webhook URLs carry their secret in the path
+    masked.alert_webhook_url = config.alert_webhook_url.as_deref().map(mask_secret);
+    for profile in &mut masked.ai_profiles {
+        profile.api_key = mask_secret(&profile.api_key);
+    }
+    // Extra headers usually carry tokens (Authorization, tenant keys)
+    for value in masked.extra_headers.values_mut() {
+        *value = mask_secret(value);
+    }
+    masked
+}
+
+// A default config that keeps the current credentials: everything `masked_config` masks (keys,
+// AI profiles, extra headers) plus the Feishu app id, webhook URLs and OpenAI org/project ids.
+fn reset_config(config: &AppConfig, include_secrets: bool) -> AppConfig {
+    if include_secrets {
+        return AppConfig::default();
+    }
+    AppConfig {
+        api_key: config.api_key.clone(),
+        feishu_app_id: config.feishu_app_id.clone(),
+        feishu_app_secret: config.feishu_app_secret.clone(),
+        webhook_token: config.webhook_token.clone(),
+        ai_profiles: config.ai_profiles.clone(),
+        extra_headers: config.extra_headers.clone(),
+        alert_webhook_url: config.alert_webhook_url.clone(),
+        report_link: config.report_link.clone(),
+        openai_org: config.openai_org.clone(),
+        openai_project: config.openai_project.clone(),
+        ..AppConfig::default()
+    }
+}
+
+// `config` with one field (by its config.json name) set back to the default.
+fn reset_config_field(config: &AppConfig, field: &str) -> AppResult<AppConfig> {
+    let to_value = |c: &AppConfig| serde_json::to_value(c).map_err(|e| AppError::Config(e.to_string()));
+    let mut value = to_value(config)?;
+    let defaults = to_value(&AppConfig::default())?;
+    let Some(default) = defaults.get(field) else {
+        return Err(AppError::Config(format!("Unknown setting '{}' (see `da config --show` for names)", field)));
+    };
+    value[field] = default.clone();
+    serde_json::from_value(value).map_err(|e| AppError::Config(e.to_string()))
+}
+
+fn save_config_file(config: &AppConfig) -> AppResult<()> {
+    let path = get_config_path();
+    let content = serde_json::to_string_pretty(config).map_err(|e| AppError::Config(e.to_string()))?;
+    std::fs::write(path, content).map_err(|e| AppError::Config(e.to_string()))?;
+    Ok(())
+}
+
+// Returns true if the commit time falls within the configured work hours.
+// Ranges that wrap past midnight (e.g. 22:00 - 06:00) are supported.
+fn within_work_hours(time: i64, config: &AppConfig) -> bool {
+    let (Some(start), Some(end)) = (&config.work_hours_start, &config.work_hours_end) else { return true; };
+    let (Ok(start), Ok(end)) = (NaiveTime::parse_from_str(start, "%H:%M"), NaiveTime::parse_from_str(end, "%H:%M")) else { return true; };
+    let Some(commit_time) = DateTime::from_timestamp(time, 0).map(|t| t.with_timezone(&Local).time()) else { return true; };
+    if start <= end {
+        commit_time >= start && commit_time <= end
+    } else {
+        commit_time >= start || commit_time <= end
+    }
+}
+
+// `{placeholders}` the export template may use and what each expands to; both the linter and
+// `expand_template` go through this list. Expansion happens before the AI sees the template.
+const TEMPLATE_PLACEHOLDERS: [(&str, fn(&[GitCommit]) -> String); 1] = [("commits", format_commit_list)];
+
+// The template with every known placeholder filled in, or None when it uses none.
+fn expand_template(template: &str, commits: &[GitCommit]) -> Option<String> {
+    let mut expanded = None;
+    for (name, expand) in TEMPLATE_PLACEHOLDERS {
+        let key = format!("{{{}}}", name);
+        let current = expanded.as_deref().unwrap_or(template);
+        if current.contains(&key) {
+            expanded = Some(current.replace(&key, &expand(commits)));
+        }
+    }
+    expanded
+}
+
+// Warnings ("line N: ...") for template mistakes: unknown placeholders, unbalanced markdown
+// emphasis, brackets or code spans, and headings with nothing under them.
+fn lint_template(template: &str) -> Vec<String> {
+    if template.trim().is_empty() {
+        return vec!["template is empty".to_string()];
+    }
+    let mut warnings: Vec<(usize, String)> = vec![];
+    let placeholder = regex::Regex::new(r"\{([^{}\s]*)\}").unwrap();
+    let lines: Vec<&str> = template.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let mut warn = |msg: String| warnings.push((i + 1, msg));
+        for cap in placeholder.captures_iter(line) {
+            if !TEMPLATE_PLACEHOLDERS.iter().any(|(name, _)| *name == &cap[1]) {
+                warn(format!("unknown placeholder {} (known: {})", &cap[0], TEMPLATE_PLACEHOLDERS.map(|(name, _)| format!("{{{}}}", name)).join(", ")));
+            }
+        }
+        let rest = placeholder.replace_all(line, "");
+        if rest.contains('{') || rest.contains('}') {
+            warn("unbalanced { }".to_string());
+        }
+        if line.matches("**").count() % 2 == 1 {
+            warn("unclosed ** (bold)".to_string());
+        }
+        // A leading "* " is a list bullet, not emphasis
+        let body = line.trim_start();
+        let body = body.strip_prefix("* ").unwrap_or(body).replace("**", "");
+        if body.matches('*').count() % 2 == 1 {
+            warn("unclosed * (italic)".to_string());
+        }
+        if line.matches('`').count() % 2 == 1 {
+            warn("unclosed ` (code)".to_string());
+        }
+        if line.matches('[').count() != line.matches(']').count() {
+            warn("unbalanced [ ]".to_string());
+        }
+    }
+    // Heading level of "# ..." lines, with lines that are entirely bold ranking below "######"
+    let heading_level = |line: &str| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            Some(line.chars().take_while(|c| *c == '#').count())
+        } else if line.len() > 4 && line.starts_with("**") && line.ends_with("**") && line.matches("**").count() == 2 {
+            Some(7)
+        } else {
+            None
+        }
+    };
+    // A section is empty when the next heading isn't nested under it; the first line is the title
+    let content: Vec<(usize, &str)> = lines.iter().copied().enumerate().filter(|(_, l)| !l.trim().is_empty()).collect();
+    for (pos, (i, line)) in content.iter().enumerate().skip(1) {
+        let Some(level) = heading_level(line) else { continue };
+        if content.get(pos + 1).is_none_or(|(_, next)| heading_level(next).is_some_and(|next| next <= level)) {
+            warnings.push((i + 1, format!("section {} is empty", line.trim())));
+        }
+    }
+    warnings.sort_by_key(|(line, _)| *line);
+    warnings.into_iter().map(|(line, msg)| format!("line {}: {}", line, msg)).collect()
+}
+
+// Expands the `{commits}` template placeholder: one line per commit with its short hash.
+fn format_commit_list(commits: &[GitCommit]) -> String {
+    if commits.is_empty() {
+        return "- (no commits)".to_string();
+    }
+    commits.iter().map(|g| {
+        let short_hash: String = g.hash.chars().take(7).collect();
+        let mut line = format!("- `{}` [{}] {}", short_hash, g.repo_name.as_deref().unwrap_or("?"), g.message);
+        if g.omitted > 0 {
+            line.push_str(&format!("\n- [{}] (and {} more)", g.repo_name.as_deref().unwrap_or("?"), g.omitted));
+        }
+        line
+    }).collect::<Vec<_>>().join("\n")
+}
+
+// Lowercased word tokens; CJK characters count as one token each since they
+// aren't separated by spaces.
+fn tokenize(text: &str) -> std::collections::HashSet<String> {
+    let mut tokens = std::collections::HashSet::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !word.is_empty() { tokens.insert(std::mem::take(&mut word)); }
+        if c.is_alphanumeric() { tokens.insert(c.to_string()); }
+    }
+    if !word.is_empty() { tokens.insert(word); }
+    tokens
+}
+
+// Jaccard similarity between the token sets of two texts.
+fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (tokenize(a), tokenize(b));
+    let union = a.union(&b).count();
+    if union == 0 { return 0.0; }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+// Log/commit pairs that likely describe the same piece of work.
+fn find_duplicates<'a>(logs: &'a [LogItem], commits: &'a [GitCommit], threshold: f32) -> Vec<(&'a str, &'a str)> {
+    if threshold <= 0.0 { return vec![]; }
+    let mut pairs = vec![];
+    for l in logs {
+        for g in commits {
+            if similarity(&l.content, &g.message) >= threshold {
+                pairs.push((l.content.as_str(), g.message.as_str()));
+            }
+        }
+    }
+    pairs
+}
+
+// The rules file when configured and readable, otherwise the inline `custom_rules`.
+fn custom_rules(config: &AppConfig) -> String {
+    let Some(path) = &config.custom_rules_path else { return config.custom_rules.clone(); };
+    match std::fs::read_to_string(path) {
+        Ok(rules) => rules,
+        Err(e) => {
+            say_err!("⚠️ Can't read rules file {} ({}), using inline rules.", path, e);
+            config.custom_rules.clone()
+        }
+    }
+}
+
+// `custom_rules` followed by every active prompt snippet.
+fn rules_with_snippets(config: &AppConfig) -> String {
+    let mut rules = custom_rules(config);
+    for snippet in config.prompt_snippets.iter().filter(|s| s.active) {
+        rules.push_str("\n- ");
+        rules.push_str(&snippet.text);
+    }
+    rules
+}
+
+// Quantitative one-liner, e.g. "Today: 8 commits across 3 repos, 12 notes logged."
+fn activity_metrics(logs: &[LogItem], commits: &[GitCommit]) -> String {
+    let repos = commits.iter().filter_map(|c| c.repo_name.as_deref()).collect::<std::collections::HashSet<_>>().len();
+    let blocked = logs.iter().filter(|l| l.log_type == "blocked").count();
+    let mut line = format!("Today: {} commits across {} repos, {} notes logged", commits.len(), repos, logs.len());
+    if blocked > 0 {
+        line.push_str(&format!(", {} blocked", blocked));
+    }
+    line.push('.');
+    line
+}
+
+// Invalid patterns are reported and skipped rather than failing the review.
+fn compile_redactions(config: &AppConfig) -> Vec<regex::Regex> {
+    config.redaction_patterns.iter().filter_map(|p| match regex::Regex::new(p) {
+        Ok(re) => Some(re),
+        Err(e) => {
+            say_err!("⚠️ Ignoring invalid redaction pattern '{}': {}", p, e);
+            None
+        }
+    }).collect()
+}
+
+fn redact(text: &str, patterns: &[regex::Regex]) -> String {
+    patterns.iter().fold(text.to_string(), |text, re| re.replace_all(&text, "[REDACTED]").into_owned())
+}
+
+// Copies of the logs and commits with sensitive content masked.
+fn redact_inputs(logs: &[LogItem], commits: &[GitCommit], config: &AppConfig) -> (Vec<LogItem>, Vec<GitCommit>) {
+    let patterns = compile_redactions(config);
+    let logs = logs.iter().cloned().map(|mut l| {
+        l.content = redact(&l.content, &patterns);
+        l
+    }).collect();
+    let commits = commits.iter().cloned().map(|mut g| {
+        g.message = redact(&g.message, &patterns);
+        g.body = g.body.map(|b| redact(&b, &patterns));
+        g.diff = g.diff.map(|d| redact(&d, &patterns));
+        g
+    }).collect();
+    (logs, commits)
+}
+
+// Expands `log_line_format`, e.g. "{n}. [{time}] {content}" -> "1. [09:30] Fixed login bug".
+fn format_log_line(format: &str, n: usize, log: &LogItem) -> String {
+    let line = format
+        .replace("{n}", &n.to_string())
+        .replace("{time}", log.timestamp.get(11..16).unwrap_or(""))
+        .replace("{type}", &log.log_type)
+        .replace("{content}", &log.content);
+    if log.completed { format!("{} (done)", line) } else { line }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeEstimate {
+    minutes: i64,
+    sessions: usize,
+}
+
+impl std::fmt::Display for TimeEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "~{}h {:02}m across {} session{}", self.minutes / 60, self.minutes % 60, self.sessions, if self.sessions == 1 { "" } else { "s" })
+    }
+}
+
+// Clusters commit times into sessions (a gap longer than `gap_minutes` starts a new one) and
+// sums their spans. Each session also gets half a gap of lead-in, since its first commit
+// comes after some work rather than at its start.
+fn estimate_active_time(times: &[i64], gap_minutes: i64) -> TimeEstimate {
+    let mut times = times.to_vec();
+    times.sort();
+    let gap = gap_minutes.max(1) * 60;
+    let mut seconds = 0;
+    let mut sessions = 0;
+    let mut start: Option<i64> = None;
+    for (i, &t) in times.iter().enumerate() {
+        let session_start = *start.get_or_insert(t);
+        let session_ends = times.get(i + 1).is_none_or(|&next| next - t > gap);
+        if session_ends {
+            seconds += t - session_start + gap / 2;
+            sessions += 1;
+            start = None;
+        }
+    }
+    TimeEstimate { minutes: seconds / 60, sessions }
+}
+
+// Prompt Generation Logic (Ported from Frontend)
+fn format_commits_for_prompt<'a>(commits: impl IntoIterator<Item = &'a GitCommit>) -> String {
+    commits.into_iter().map(|g| {
+        let mut text = match &g.branch {
+            Some(branch) => format!("- [{}] ({}) {}", g.repo_name.as_deref().unwrap_or("?"), branch, g.message),
+            None => format!("- [{}] {}", g.repo_name.as_deref().unwrap_or("?"), g.message),
+        };
+        if let Some(body) = &g.body {
+            text.push_str(&format!("\n  Details:\n  {}", body.lines().collect::<Vec<_>>().join("\n  ")));
+        }
+        if let Some(diff) = &g.diff {
+             text.push_str(&format!("\n  Code Diff Summary:\n```\n{}\n```", diff));
+        }
+        if g.omitted > 0 {
+            text.push_str(&format!("\n- [{}] (and {} more)", g.repo_name.as_deref().unwrap_or("?"), g.omitted));
+        }
+        text
+    }).collect::<Vec<_>>().join("\n")
+}
+
+// Groups commits under the first ticket id (`ticket_regex`) in their message, with notes that
+// mention the same ticket; commits without one go under "Misc". Also returns the ids of the
+// notes that were placed under a ticket, so they aren't listed twice.
+fn group_by_ticket(logs: &[LogItem], commits: &[GitCommit], re: &regex::Regex) -> (String, Vec<i64>) {
+    let mut tickets: Vec<(String, Vec<&GitCommit>)> = vec![];
+    let mut misc = vec![];
+    for c in commits {
+        match re.find(&c.message) {
+            Some(m) => match tickets.iter_mut().find(|(t, _)| t == m.as_str()) {
+                Some((_, group)) => group.push(c),
+                None => tickets.push((m.as_str().to_string(), vec![c])),
+            },
+            None => misc.push(c),
+        }
+    }
+
+    let mut grouped_ids = vec![];
+    let mut sections = vec![];
+    for (ticket, group) in &tickets {
+        let mut text = format!("### {}\n{}", ticket, format_commits_for_prompt(group.iter().copied()));
+        for l in logs.iter().filter(|l| l.log_type != "blocked" && re.find_iter(&l.content).any(|m| m.as_str() == ticket)) {
+            text.push_str(&format!("\n- Note: {}", l.content));
+            grouped_ids.extend(l.id);
+        }
+        sections.push(text);
+    }
+    if !misc.is_empty() {
+        sections.push(format!("### Misc\n{}", format_commits_for_prompt(misc)));
+    }
+    (sections.join("\n"), grouped_ids)
+}
+
+// Rough token count: about 4 ASCII characters per token, one per CJK (or other) character.
+fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(char::is_ascii).count();
+    ascii / 4 + (text.chars().count() - ascii)
+}
+
+// Drops commit detail until `measure` fits the budget, following `strategy`. Diff strategies
+// fall back to dropping the oldest commits once no diffs are left; dropped commits are
+// counted in the "(and N more)" line of their repo. Logs are never dropped.
+fn trim_to_budget(commits: &mut Vec<GitCommit>, budget: usize, strategy: &str, measure: impl Fn(&[GitCommit]) -> usize) {
+    // Ties go to the later entry, since git lists commits newest first
+    let (mut dropped_diffs, mut dropped_commits) = (0, 0);
+    while !commits.is_empty() && measure(commits) > budget {
+        let has_diffs = commits.iter().any(|c| c.diff.is_some());
+        match strategy {
+            "all_diffs" if has_diffs => {
+                for c in commits.iter_mut() {
+                    dropped_diffs += c.diff.take().is_some() as usize;
+                }
+            }
+            "oldest_diffs" if has_diffs => {
+                if let Some(c) = commits.iter_mut().filter(|c| c.diff.is_some()).max_by_key(|c| std::cmp::Reverse(c.time)) {
+                    c.diff = None;
+                    dropped_diffs += 1;
+                }
+            }
+            _ => {
+                let Some(oldest) = commits.iter().enumerate().max_by_key(|(_, c)| std::cmp::Reverse(c.time)).map(|(i, _)| i) else { break };
+                let removed = commits.remove(oldest);
+                if let Some(c) = commits.iter_mut().filter(|c| c.repo_name == removed.repo_name).max_by_key(|c| std::cmp::Reverse(c.time)) {
+                    c.omitted += 1 + removed.omitted;
+                }
+                dropped_commits += 1;
+            }
+        }
+    }
+    if dropped_diffs + dropped_commits > 0 {
+        say_err!("✂️ Prompt over budget: dropped {} diff(s) and {} commit(s)", dropped_diffs, dropped_commits);
+    }
+}
+
+fn generate_prompt_logic(logs: &[LogItem], commits: &[GitCommit], config: &AppConfig, mode: &str) -> String {
+    let (logs, mut commits) = redact_inputs(logs, commits, config);
+    let context = collect_extra_context(config);
+    if let Some(budget) = config.max_prompt_tokens {
+        trim_to_budget(&mut commits, budget, &config.truncation_strategy, |c| {
+            estimate_tokens(&generate_prompt_with_git_text(&logs, c, &format_commits_for_prompt(c), &[], &context, config, mode))
+        });
+    }
+    let ticket_re = config.ticket_regex.as_deref().and_then(|p| regex::Regex::new(p).map_err(|e| say_err!("⚠️ Ignoring invalid ticket regex: {}", e)).ok());
+    match ticket_re {
+        Some(re) => {
+            let (git_text, grouped_ids) = group_by_ticket(&logs, &commits, &re);
+            let git_text = format!("(Grouped by ticket; organize the report under these ticket headings, with Misc last.)\n{}", git_text);
+            generate_prompt_with_git_text(&logs, &commits, &git_text, &grouped_ids, &context, config, mode)
+        }
+        None => generate_prompt_with_git_text(&logs, &commits, &format_commits_for_prompt(&commits), &[], &context, config, mode),
+    }
+}
+
+const CONTEXT_OUTPUT_LIMIT: usize = 4000;
+const CONTEXT_COMMAND_TIMEOUT_SECS: u64 = 10;
+
+// Runs a context command through the shell, giving up after CONTEXT_COMMAND_TIMEOUT_SECS.
+fn run_context_command(cmd: &str) -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", cmd]).creation_flags(0x08000000);
+        c
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut command = {
+        let mut c = Command::new("sh");
+        c.args(["-c", cmd]);
+        c
+    };
+    let mut child = command.stdout(std::process::Stdio::piped()).stderr(std::process::Stdio::null()).spawn().map_err(|e| e.to_string())?;
+    // Read on another thread so a chatty command can't block on a full pipe while we wait
+    let mut stdout = child.stdout.take().ok_or("no stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = vec![];
+        let _ = std::io::Read::read_to_end(&mut stdout, &mut buf);
+        buf
+    });
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(CONTEXT_COMMAND_TIMEOUT_SECS);
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => return Ok(String::from_utf8_lossy(&reader.join().unwrap_or_default()).into_owned()),
+            Some(status) => return Err(format!("exited with {}", status)),
+            None if std::time::Instant::now() >= deadline => {
+                let _ = child.kill();
+                // Reap it, or every timeout leaves a zombie behind in the daemon
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", CONTEXT_COMMAND_TIMEOUT_SECS));
+            }
+            None => std::thread::sleep(std::time::Duration::from_millis(50)),
+        }
+    }
+}
+
+// Labeled, redacted output of every `context_commands` entry. Long outputs keep their end,
+// which for history-like commands is the most recent part.
+fn collect_command_context(config: &AppConfig) -> String {
+    let patterns = compile_redactions(config);
+    config.context_commands.iter().filter_map(|cmd| match run_context_command(cmd) {
+        Ok(out) if out.trim().is_empty() => None,
+        Ok(out) => {
+            let out = out.trim_end();
+            let count = out.chars().count();
+            let out = if count > CONTEXT_OUTPUT_LIMIT {
+                format!("(truncated)...{}", out.chars().skip(count - CONTEXT_OUTPUT_LIMIT).collect::<String>())
+            } else {
+                out.to_string()
+            };
+            Some(format!("$ {}\n{}", cmd, redact(&out, &patterns)))
+        }
+        Err(e) => {
+            say_err!("⚠️ Context command `{}` failed: {}", cmd, e);
+            None
+        }
+    }).collect::<Vec<_>>().join("\n\n")
+}
+
+const WIP_FILE_LIMIT: usize = 10;
+
+// Uncommitted changes and stashes of one repo, e.g. "- [api] 2 changed, 1 untracked: a.rs, b.rs, c.rs"
+// plus a "Stashed:" line. None when the working tree is clean and nothing is stashed.
+fn repo_wip(path: &str, name: &str) -> Option<String> {
+    let git = |args: &[&str]| Command::new("git").args(["-C", path]).args(args).output().ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        .unwrap_or_default();
+    let status = git(&["status", "--porcelain"]);
+    let stash = git(&["stash", "list", "--format=%gs"]);
+    let files: Vec<(&str, &str)> = status.lines().filter_map(|l| Some((l.get(..2)?, l.get(3..)?))).collect();
+    let stashes: Vec<&str> = stash.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if files.is_empty() && stashes.is_empty() {
+        return None;
+    }
+    let mut text = format!("- [{}]", name);
+    if !files.is_empty() {
+        let untracked = files.iter().filter(|(state, _)| *state == "??").count();
+        let mut counts = vec![];
+        if files.len() > untracked { counts.push(format!("{} changed", files.len() - untracked)); }
+        if untracked > 0 { counts.push(format!("{} untracked", untracked)); }
+        let mut list = files.iter().take(WIP_FILE_LIMIT).map(|(_, f)| *f).collect::<Vec<_>>().join(", ");
+        if files.len() > WIP_FILE_LIMIT {
+            list.push_str(&format!(" (and {} more)", files.len() - WIP_FILE_LIMIT));
+        }
+        text.push_str(&format!(" {}: {}", counts.join(", "), list));
+    }
+    if !stashes.is_empty() {
+        text.push_str(&format!("\n  Stashed: {}", stashes.join("; ")));
+    }
+    Some(text)
+}
+
+// Prompt sections that don't come from logs or commits: `context_commands` output and, with
+// `include_wip`, uncommitted work. Both shell out, so this runs once per review.
+fn collect_extra_context(config: &AppConfig) -> String {
+    let mut text = String::new();
+    let commands = collect_command_context(config);
+    if !commands.is_empty() {
+        text.push_str(&format!("\n        Other Activity (command output, for work outside git; mention only what is work-relevant):\n{}\n", commands));
+    }
+    if config.include_wip {
+        let patterns = compile_redactions(config);
+        let wip = config.git_paths.iter()
+            .filter_map(|path| repo_wip(path, &repo_display_name(config, path)))
+            .map(|w| redact(&w, &patterns))
+            .collect::<Vec<_>>().join("\n");
+        if !wip.is_empty() {
+            text.push_str(&format!("\n        Work in Progress (uncommitted changes and stashes; present as ongoing, not finished, work):\n{}\n", wip));
+        }
+    }
+    text
+}
+
+// Instruction to lead with `focus`, listing the commits (by repo name) and logs (by `#tag`) that belong to it.
+fn focus_instruction(focus: &str, logs: &[LogItem], commits: &[GitCommit]) -> String {
+    let tag = format!("#{}", focus.to_lowercase());
+    let repos = commits.iter()
+        .filter_map(|c| c.repo_name.as_deref())
+        .filter(|name| name.eq_ignore_ascii_case(focus))
+        .count();
+    let notes = logs.iter().filter(|l| l.content.to_lowercase().contains(&tag)).count();
+    if repos + notes == 0 {
+        say_err!("⚠️ Focus project '{}' matches no repo or {} note today", focus, tag);
+    }
+    format!(
+        "\n\nFocus: today's priority is the project \"{}\" (commits in the [{}] repo and notes tagged {}; {} commit(s), {} note(s)). Lead the report with this work and expand on it with specifics; summarize everything else briefly at the end.",
+        focus, focus, tag, repos, notes
+    )
+}
+
+// `git_text` is normally the formatted commit list; chunked reviews pass the
+// per-batch summaries instead. Logs in `skip_log_ids` are already part of `git_text`.
+// `context` holds the extra prompt sections from `collect_extra_context`.
+fn generate_prompt_with_git_text(logs: &[LogItem], commits: &[GitCommit], git_text: &str, skip_log_ids: &[i64], context: &str, config: &AppConfig, mode: &str) -> String {
+    let logs_text = logs.iter().filter(|l| l.log_type != "blocked" && !l.id.is_some_and(|id| skip_log_ids.contains(&id))).enumerate().map(|(i, l)| format_log_line(&config.log_line_format, i + 1, l)).collect::<Vec<_>>().join("\n");
+    let blocked: Vec<_> = logs.iter().filter(|l| l.log_type == "blocked").map(|l| format!("- {}", l.content)).collect();
+    let blocked_text = if blocked.is_empty() {
+        String::new()
+    } else {
+        format!("\n        Blocked (waiting on someone else; list these prominently under 问题与对策 with who/what they are waiting on):\n{}\n", blocked.join("\n"))
+    };
+
+    let base_instruction = if mode == "analysis" {
+        "Provide a comprehensive summary, 3 improvements, and 1 key knowledge point. If code diffs are provided, use them to explain technical details.".to_string()
+    } else if mode == "summary" {
+        "Reply with a one-sentence TL;DR of today's work (at most 40 words, plain text, no heading, bullets or line breaks). This overrides any output format in the rules below.".to_string()
+    } else if mode == "plan" {
+        // Tasks logged before the cutoff are the morning plan; everything else is what actually happened.
+        // A hand-edited cutoff that isn't HH:MM falls back to the default of noon.
+        let cutoff = NaiveTime::parse_from_str(&config.plan_cutoff, "%H:%M").unwrap_or(NaiveTime::MIN + chrono::Duration::hours(12));
+        let planned = logs.iter()
+            .filter(|l| l.log_type == "task" && l.timestamp.get(11..16).and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok()).is_some_and(|t| t < cutoff))
+            .map(|l| format!("- {} [{}]", l.content, if l.completed { "completed" } else { "not marked complete" }))
+            .collect::<Vec<_>>();
+        format!(
+            "Compare what was planned this morning against what was actually done today (completed tasks, later logs and git commits). For each planned item, state whether it was achieved, deferred, or abandoned, citing the evidence. Then briefly list unplanned work that took time.\n\nPlanned This Morning (before {}):\n{}",
+            cutoff.format("%H:%M"),
+            if planned.is_empty() { "- (no tasks logged)".to_string() } else { planned.join("\n") }
+        )
+    } else { // export
+        // Template placeholders like `{commits}` are filled in here with factual data,
+        // while the [bracketed] sections are left for the AI to write.
+        match expand_template(&config.report_template, commits) {
+            Some(template) => format!(
+                "Strictly follow the format below. Fill in the [bracketed] sections; any commit list already present in the template is factual data and must be reproduced verbatim.\n\nFormat Template:\n{}",
+                template
+            ),
+            None => format!("Strictly follow the format below:\n\nFormat Template:\n{}", config.report_template),
+        }
+    };
+
+    let base_instruction = match &config.focus_project {
+        Some(focus) => base_instruction + &focus_instruction(focus, logs, commits),
+        None => base_instruction,
+    };
+
+    let duplicates = find_duplicates(logs, commits, config.dedupe_threshold);
+    let dedupe_text = if duplicates.is_empty() {
+        String::new()
+    } else {
+        let pairs = duplicates.iter().map(|(log, commit)| format!("- Log \"{}\" ≈ Commit \"{}\"", log, commit)).collect::<Vec<_>>().join("\n");
+        format!("\n        Possible Duplicates (the same work recorded twice; merge each pair into a single item instead of listing both):\n{}\n", pairs)
+    };
+
+    let mut metrics_text = if config.include_metrics && mode != "summary" {
+        format!("\n        Metrics (open the report with this line, unchanged):\n        {}\n", activity_metrics(logs, commits))
+    } else {
+        String::new()
+    };
+    if config.include_time_estimate && mode != "summary" && !commits.is_empty() {
+        let estimate = estimate_active_time(&commits.iter().map(|c| c.time).collect::<Vec<_>>(), config.time_gap_minutes);
+        metrics_text.push_str(&format!("\n        Estimated active time (rough, inferred from commit timestamps): {}\n", estimate));
+    }
+
+    format!(r#"{}
+        Context:
+        Manual Logs:
+        {}
+        
+        {}
+        Git Commits:
+        {}
+        {}{}
+        System Instruction:
+        {}
+        
+        Additional User Rules:
+        {}
+    "#, metrics_text, logs_text, blocked_text, git_text, dedupe_text, context, base_instruction, rules_with_snippets(config))
+}
+
+
+// Tauri Commands
+// Joined onto the base URL, so it has to be an absolute path.
+fn check_completions_path(path: &str) -> AppResult<()> {
+    if path.starts_with('/') { Ok(()) } else { Err(AppError::Config(format!("Completions path must start with '/', got: {}", path))) }
+}
+
+// Checks shared by every way of saving the config (CLI flags and the GUI).
+fn validate_config(config: &AppConfig) -> AppResult<()> {
+    if let Some(path) = config.completions_path.as_deref().filter(|p| !p.is_empty()) {
+        check_completions_path(path)?;
+    }
+    Ok(())
+}
+
+// Extra header values and the alert webhook URL come back masked as in `config --show`;
+// `save_config` restores any that are still the masked form of the stored value.
+#[tauri::command]
+fn get_config() -> AppConfig {
+    let config = load_config();
+    let masked = masked_config(&config);
+    AppConfig { extra_headers: masked.extra_headers, alert_webhook_url: masked.alert_webhook_url, ..config }
+}
+
+#[tauri::command]
+fn save_config(mut config: AppConfig) -> AppResult<()> {
+    let stored = load_config();
+    for (name, value) in config.extra_headers.iter_mut() {
+        if let Some(old) = stored.extra_headers.get(name).filter(|old| mask_secret(old) == *value) {
+            *value = old.clone();
+        }
+    }
+    if let Some(old) = stored.alert_webhook_url.filter(|old| config.alert_webhook_url.as_deref() == Some(mask_secret(old).as_str())) {
+        config.alert_webhook_url = Some(old);
+    }
+    validate_config(&config)?;
+    save_config_file(&config)
+}
+
+
+#[tauri::command]
+fn save_log(state: State<DbState>, content: String, log_type: String) -> AppResult<String> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    let (content, truncated) = limit_log_length(&content, &load_config());
+    insert_log(&conn, &content, &log_type)?;
+    Ok(match truncated {
+        Some(len) => format!("Log saved, but truncated ({} characters was over the limit)", len),
+        None => "Log saved successfully".to_string(),
+    })
+}
+
+#[tauri::command]
+fn delete_log(state: State<DbState>, id: i64) -> AppResult<String> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    if conn.execute("DELETE FROM logs WHERE id = ?1", [&id])? == 0 {
+        return Err(AppError::NotFound(format!("Log ID {}", id)));
+    }
+    Ok("Log deleted successfully".to_string())
+}
+
+// Newest first and unlimited unless `order` ("asc" or "desc") / `limit` say otherwise.
+#[tauri::command]
+fn get_today_logs(state: State<DbState>, log_type: Option<String>, order: Option<String>, limit: Option<usize>) -> AppResult<Vec<LogItem>> {
+    let order = match order.as_deref() {
+        None | Some("desc") => "DESC",
+        Some("asc") => "ASC",
+        Some(o) => return Err(AppError::Config(format!("Unknown order '{}' (expected asc or desc)", o))),
+    };
+    let mut filter = "WHERE date(timestamp) = date('now', 'localtime')".to_string();
+    let mut params = vec![];
+    match log_type {
+        None => {}
+        Some(t) if LOG_TYPES.contains(&t.as_str()) => {
+            filter.push_str(" AND log_type = ?1");
+            params.push(t);
+        }
+        Some(t) => return Err(AppError::Config(format!("Unknown log type '{}' (expected one of {})", t, LOG_TYPES.join(", ")))),
+    }
+    filter.push_str(&format!(" ORDER BY id {}", order));
+    if let Some(limit) = limit {
+        filter.push_str(&format!(" LIMIT {}", limit));
+    }
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    query_logs(&conn, &filter, rusqlite::params_from_iter(params))
+}
+
+#[tauri::command]
+fn complete_log(state: State<DbState>, id: i64, completed: bool) -> AppResult<()> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    set_log_completed(&conn, id, completed)
+}
+
+#[tauri::command]
+fn purge_logs(state: State<DbState>, older_than_days: i64) -> AppResult<usize> {
+    let mut conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    purge_logs_older_than(&mut conn, older_than_days)
+}
+
+#[tauri::command]
+fn search_logs_regex(state: State<DbState>, pattern: String) -> AppResult<Vec<LogItem>> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    search_logs_matching(&conn, &pattern)
+}
+
+#[tauri::command]
+fn get_full_diff(state: State<DbState>, hash: String) -> AppResult<String> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    load_full_diff(&conn, &hash)?.map(|(_, diff)| diff).ok_or_else(|| AppError::NotFound(format!("No stored diff for {}", hash)))
+}
+
+#[tauri::command]
+fn db_stats(state: State<DbState>) -> AppResult<DbStats> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    db_stats_of(&conn, &get_db_path())
+}
+
+#[tauri::command]
+fn get_tags(state: State<DbState>) -> AppResult<Vec<(String, usize)>> {
+    let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    tag_counts(&conn)
+}
+
+#[tauri::command]
+fn merge_db(state: State<DbState>, other_path: String) -> AppResult<MergeReport> {
+    let mut conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+    merge_logs_from(&mut conn, &other_path)
+}
+
+// The configured display name for a repo path, else its directory name.
+fn repo_display_name(config: &AppConfig, path: &str) -> String {
+    match config.repo_names.get(path) {
+        Some(name) => name.clone(),
+        None => std::path::Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or("Unknown").to_string(),
+    }
+}
+
+// Drops `git_paths` entries that resolve (following symlinks) to the same directory as an earlier
+// one, moving their display name over if the kept path has none. Returns the (removed, kept)
+// pairs and the paths that don't exist, which are left in place.
+fn dedupe_repo_paths(config: &mut AppConfig) -> (Vec<(String, String)>, Vec<String>) {
+    let mut seen: Vec<(std::path::PathBuf, String)> = vec![];
+    let (mut merged, mut missing, mut kept) = (vec![], vec![], vec![]);
+    for path in std::mem::take(&mut config.git_paths) {
+        let canonical = match std::fs::canonicalize(&path) {
+            Ok(c) => c,
+            Err(_) => {
+                missing.push(path.clone());
+                kept.push(path);
+                continue;
+            }
+        };
+        match seen.iter().find(|(c, _)| *c == canonical) {
+            Some((_, first)) => {
+                if let Some(name) = config.repo_names.remove(&path) {
+                    config.repo_names.entry(first.clone()).or_insert(name);
+                }
+                merged.push((path, first.clone()));
+            }
+            None => {
+                seen.push((canonical, path.clone()));
+                kept.push(path);
+            }
+        }
+    }
+    config.git_paths = kept;
+    (merged, missing)
+}
+
+// Repos are matched by configured display name, directory name or full path.
+fn filter_repo_paths(config: &AppConfig, include: &[String], exclude: &[String]) -> Vec<String> {
+    let matches = |path: &String, names: &[String]| {
+        let dir = std::path::Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let display = repo_display_name(config, path);
+        names.iter().any(|n| n == path || n == dir || *n == display)
+    };
+    for name in include.iter().chain(exclude) {
+        if !config.git_paths.iter().any(|p| matches(p, std::slice::from_ref(name))) {
+            say_err!("⚠️ Unknown repo: {}", name);
+        }
+    }
+    config.git_paths.iter()
+        .filter(|p| include.is_empty() || matches(p, include))
+        .filter(|p| !matches(p, exclude))
+        .cloned()
+        .collect()
+}
+
+// Commit counts per author across all configured repos, highest first. Authors listed in
+// `author_aliases` are merged under their canonical name.
+fn count_contributors(config: &AppConfig, since: &str) -> Vec<(String, usize)> {
+    let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for path in &config.git_paths {
+        // shortlog reads from stdin unless given a revision
+        let output = Command::new("git").args(["-C", path, "shortlog", "-sn", &format!("--since={}", since), "HEAD"]).output();
+        let Ok(out) = output else { continue };
+        if !out.status.success() {
+            if repo_has_commits(path) {
+                say_err!("⚠️ git shortlog failed for {}: {}", path, String::from_utf8_lossy(&out.stderr).trim());
+            }
+            continue;
+        }
+        for line in String::from_utf8_lossy(&out.stdout).lines() {
+            let Some((count, name)) = line.trim().split_once('\t') else { continue };
+            let name = config.author_aliases.get(name).cloned().unwrap_or_else(|| name.to_string());
+            *counts.entry(name).or_default() += count.trim().parse::<usize>().unwrap_or(0);
+        }
+    }
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+// Commits in `range` (e.g. "abc123..HEAD", or "-1" for the latest), newest first.
+fn commits_in_range(path: &str, range: &str) -> AppResult<Vec<GitCommit>> {
+    let out = Command::new("git")
+        .args(["-C", path, "log", "--source", "--pretty=format:%H%x1f%an%x1f%at%x1f%S%x1f%s%x1e", range])
+        .output()
+        .map_err(|e| AppError::Git(e.to_string()))?;
+    if !out.status.success() {
+        return Err(AppError::Git(String::from_utf8_lossy(&out.stderr).trim().to_string()));
+    }
+    Ok(parse_git_log(&String::from_utf8_lossy(&out.stdout)))
+}
+
+// The repo's configured identity (`user.email`, falling back to `user.name`),
+// used to keep only your own commits in shared repos.
+fn detect_git_author(path: &str) -> Option<String> {
+    ["user.email", "user.name"].iter().find_map(|key| {
+        let out = Command::new("git").args(["-C", path, "config", key]).output().ok()?;
+        let value = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        (out.status.success() && !value.is_empty()).then_some(value)
+    })
+}
+
+// False for freshly `git init`ed repos, where HEAD doesn't point at a commit yet.
+// A detached HEAD still resolves, so it counts as having commits.
+fn repo_has_commits(path: &str) -> bool {
+    Command::new("git")
+        .args(["-C", path, "rev-parse", "--verify", "--quiet", "HEAD"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// "refs/heads/feature/x" -> "feature/x". `--source` reports a commit reachable from
+// several branches under the first ref git walked, and plain HEAD when not scanning all refs.
+fn short_ref(source: &str) -> Option<String> {
+    let name = ["refs/heads/", "refs/remotes/", "refs/tags/"].iter()
+        .find_map(|prefix| source.strip_prefix(prefix))
+        .unwrap_or(source);
+    if name.is_empty() || name == "HEAD" { None } else { Some(name.to_string()) }
+}
+
+// Parses `git log` output produced with the format used by `scan_git_repos`.
+// Fields are separated by 0x1F and commits terminated by 0x1E, so subjects may
+// contain any printable character (including `|`) and bodies may span lines.
+// The subject comes last (before the optional body) and is split with
+// `splitn`, so it is never broken up further.
+fn parse_git_log(output: &str) -> Vec<GitCommit> {
+    output.split('\x1e').filter_map(|record| {
+        let record = record.trim_start_matches(['\r', '\n']);
+        let mut fields = record.splitn(6, '\x1f');
+        let hash = fields.next().filter(|h| !h.is_empty())?.to_string();
+        let author = fields.next()?.to_string();
+        let time = fields.next()?.parse().unwrap_or(0);
+        let branch = short_ref(fields.next()?);
+        let message = fields.next()?.to_string();
+        let body = fields.next().map(str::trim).filter(|b| !b.is_empty()).map(str::to_string);
+        Some(GitCommit { hash, message, author, time, repo_name: None, diff: None, body, omitted: 0, branch, full_diff: None })
+    }).collect()
+}
+
+// Normalizes an `--until` bound to "YYYY-MM-DD HH:MM:SS". A bare date means the end of that day
+// (git alone would take the current time of day on it) and a bare time means that time on `day`.
+fn parse_until(until: &str, day: chrono::NaiveDate) -> AppResult<String> {
+    let until = until.trim();
+    let parsed = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"].iter()
+        .find_map(|f| chrono::NaiveDateTime::parse_from_str(until, f).ok())
+        .or_else(|| chrono::NaiveDate::parse_from_str(until, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(23, 59, 59)))
+        .or_else(|| ["%H:%M:%S", "%H:%M"].iter().find_map(|f| NaiveTime::parse_from_str(until, f).ok()).map(|t| day.and_time(t)));
+    parsed
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .ok_or_else(|| AppError::Config(format!("Invalid --until '{}' (expected YYYY-MM-DD, HH:MM or YYYY-MM-DD HH:MM)", until)))
+}
+
+#[tauri::command]
+fn scan_git_repos(paths: Vec<String>, deep_analysis: bool, until: Option<String>) -> AppResult<Vec<GitCommit>> {
+    scan_git_repos_on(paths, deep_analysis, None, until.as_deref())
+}
+
+// Commits made on `date` (local time), or today when None, up to `until` (see `parse_until`;
+// the end of `date` by default, no bound for today).
+fn scan_git_repos_on(paths: Vec<String>, deep_analysis: bool, date: Option<chrono::NaiveDate>, until: Option<&str>) -> AppResult<Vec<GitCommit>> {
+    let since = match date {
+        Some(d) => format!("--since={} 00:00:00", d),
+        None => "--since=midnight".to_string(),
+    };
+    let until = match (until, date) {
+        (Some(u), _) => Some(format!("--until={}", parse_until(u, date.unwrap_or_else(|| Local::now().date_naive()))?)),
+        (None, Some(d)) => Some(format!("--until={} 23:59:59", d)),
+        (None, None) => None,
+    };
+    scan_git_window(paths, deep_analysis, &since, until.as_deref())
+}
+
+// stderr of git failures caused by another git process (an editor or IDE) touching the repo at
+// the same moment; these are worth retrying, anything else (not a repo, bad revision) isn't.
+const TRANSIENT_GIT_ERRORS: [&str; 4] = ["index.lock", "Another git process", "cannot lock ref", "Unable to create"];
+
+// Runs git, retrying up to `retries` more times on a TRANSIENT_GIT_ERRORS failure.
+fn run_git(args: &[&str], retries: usize) -> std::io::Result<std::process::Output> {
+    let mut attempt = 0;
+    loop {
+        let out = Command::new("git").args(args).output()?;
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let transient = !out.status.success() && TRANSIENT_GIT_ERRORS.iter().any(|e| stderr.contains(e));
+        if !transient || attempt >= retries {
+            return Ok(out);
+        }
+        attempt += 1;
+        say_err!("⚠️ git is busy ({}), retrying ({}/{})...", stderr.lines().next().unwrap_or_default().trim(), attempt, retries);
+        std::thread::sleep(std::time::Duration::from_millis(300 * attempt as u64));
+    }
+}
+
+// Commits between the `--since=...` and optional `--until=...` git arguments.
+fn scan_git_window(paths: Vec<String>, deep_analysis: bool, since: &str, until: Option<&str>) -> AppResult<Vec<GitCommit>> {
+    let config = load_config();
+    // (repo path, commit) pairs; diffs are fetched afterwards, once the total is known
+    let mut scanned = Vec::new();
+
+    let repo_count = paths.len();
+    for (i, path) in paths.into_iter().enumerate() {
+        let repo_name = repo_display_name(&config, &path);
+        if deep_analysis {
+            report_scan_progress(ScanProgress { stage: "repo", name: repo_name.clone(), current: i + 1, total: repo_count });
+        }
+        
+        let format = if config.include_commit_body { "--pretty=format:%H%x1f%an%x1f%at%x1f%S%x1f%s%x1f%b%x1e" } else { "--pretty=format:%H%x1f%an%x1f%at%x1f%S%x1f%s%x1e" };
+        let author = config.git_author.clone().or_else(|| if config.detect_git_author { detect_git_author(&path) } else { None });
+        // git matches --author as a regex against "Name <email>"; match it literally instead, and
+        // wrap emails in <> so "bob@x.com" doesn't also pick up "jimbob@x.com"
+        let author_arg = author.map(|a| if a.contains('@') { format!("--author=<{}>", a) } else { format!("--author={}", a) });
+        let mut args = vec!["-C", &path, "log", since, "--source", format];
+        if let Some(u) = until { args.push(u); }
+        if config.scan_all_branches { args.extend(["--exclude=refs/stash", "--all"]); }
+        if let Some(a) = &author_arg { args.extend(["--fixed-strings", a]); }
+        let output = run_git(&args, config.git_retries);
+
+        if let Ok(out) = output {
+             if out.status.success() {
+                 let stdout = String::from_utf8_lossy(&out.stdout);
+                 // git log lists newest first, so truncating keeps the most recent commits
+                 let mut commits: Vec<GitCommit> = parse_git_log(&stdout).into_iter().filter(|c| within_work_hours(c.time, &config)).collect();
+                 if let Some(max) = config.max_commits_per_repo.filter(|&max| max > 0 && commits.len() > max) {
+                     let omitted = commits.len() - max;
+                     commits.truncate(max);
+                     if let Some(last) = commits.last_mut() { last.omitted = omitted; }
+                 }
+                 for mut commit in commits {
+                     commit.repo_name = Some(repo_name.clone());
+                     scanned.push((path.clone(), commit));
+                 }
+             } else if repo_has_commits(&path) {
+                 // (A repo without commits fails `git log` too, but that just means zero commits.)
+                 say_err!("⚠️ git log failed for {}: {}", path, String::from_utf8_lossy(&out.stderr).trim());
+             }
+        }
+    }
+
+    let deep_analysis = match config.auto_deep_threshold {
+        Some(threshold) if deep_analysis && scanned.len() >= threshold => {
+            if !quiet() { say_err!("⚡ {} commits (auto-deep threshold {}), skipping code diffs.", scanned.len(), threshold); }
+            false
+        }
+        Some(threshold) if deep_analysis => {
+            if !quiet() { say_err!("🔬 {} commits (below auto-deep threshold {}), fetching code diffs.", scanned.len(), threshold); }
+            true
+        }
+        _ => deep_analysis,
+    };
+
+    let mut all_commits = Vec::new();
+    let commit_count = scanned.len();
+    for (i, (path, mut commit)) in scanned.into_iter().enumerate() {
+        if deep_analysis {
+            let name = format!("{} {}", commit.hash.chars().take(7).collect::<String>(), commit.repo_name.as_deref().unwrap_or_default());
+            report_scan_progress(ScanProgress { stage: "diff", name, current: i + 1, total: commit_count });
+            let mut diff_args = vec!["-C", &path, "show", &commit.hash, "--pretty=", "--patch", "--max-count=1"];
+            if let Some(u) = until { diff_args.push(u); }
+            let dout = run_git(&diff_args, config.git_retries);
+            
+            if let Ok(d) = dout {
+                let raw = String::from_utf8_lossy(&d.stdout).to_string();
+                if raw.chars().count() > 3000 {
+                    commit.diff = Some(format!("{}... (truncated)", raw.chars().take(3000).collect::<String>()));
+                    if config.keep_full_diffs { commit.full_diff = Some(raw); }
+                } else {
+                    commit.diff = Some(raw);
+                }
+            }
+        }
+        all_commits.push(commit);
+    }
+    if config.commit_order == "chronological" {
+        all_commits.sort_by_key(|c| c.time);
+    }
+    Ok(all_commits)
+}
+
+// Progress of a deep scan: each repo being scanned, then each commit whose diff is fetched.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    stage: &'static str, // "repo" or "diff"
+    name: String,
+    current: usize,
+    total: usize,
+}
+
+// Set in GUI mode; scan progress then goes to the window as "scan-progress" events.
+static PROGRESS_APP: std::sync::OnceLock<AppHandle> = std::sync::OnceLock::new();
+
+// Set by `--quiet` and by `--json` output, which hide scan progress in the CLI.
+static QUIET: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn quiet() -> bool {
+    QUIET.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// Progress goes to stderr so it never mixes with a report on stdout.
+fn report_scan_progress(progress: ScanProgress) {
+    if let Some(app) = PROGRESS_APP.get() {
+        let _ = app.emit("scan-progress", &progress);
+        return;
+    }
+    if quiet() {
+        return;
+    }
+    match progress.stage {
+        "repo" => say_err!("🔎 Scanning repo {} ({}/{})", progress.name, progress.current, progress.total),
+        _ => say_err!("📄 Fetching diff for commit {}/{} ({})", progress.current, progress.total, progress.name),
+    }
+}
+
+// Set by the global `--offline` flag: AI requests get a stub reply and sinks and alert webhooks
+// send nothing, so log/commit collection and prompts can be checked without credentials.
+static OFFLINE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn offline() -> bool {
+    OFFLINE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// Stands in for the AI reply in offline mode; structured requests get a schema-valid report.
+fn offline_ai_reply(request: &AiRequest) -> String {
+    let text = format!("[offline mode] AI call to {} skipped (~{} prompt tokens).", request.model, estimate_tokens(&request.prompt));
+    if request.response_format.is_some() {
+        serde_json::json!({ "summary": text, "tasks": [], "problems": [] }).to_string()
+    } else {
+        text
+    }
+}
+
+// Set by `--plain`, or by a non-empty NO_EMOJI / NO_COLOR environment variable.
+static PLAIN: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// Emoji that carry meaning get an ASCII marker in plain output; all others are dropped.
+const PLAIN_MARKERS: [(&str, &str); 9] = [
+    ("❌", "[error]"),
+    ("⚠️", "[warn]"),
+    ("⚠", "[warn]"),
+    ("✅", "[ok]"),
+    ("✓", "[ok]"),
+    ("✗", "[--]"),
+    ("⛔", "[blocked]"),
+    ("→", "->"),
+    ("…", "..."),
+];
+
+// An emoji with its variation selector / joiners and one trailing space, removed in plain mode.
+static PICTOGRAPH: std::sync::LazyLock<regex::Regex> =
+    std::sync::LazyLock::new(|| regex::Regex::new(r"\p{Extended_Pictographic}[\u{FE0F}\u{200D}]* ?").unwrap());
+
+// A CLI output line as printed: unchanged normally, ASCII markers in plain mode.
+fn styled(text: &str) -> String {
+    if !PLAIN.load(std::sync::atomic::Ordering::Relaxed) {
+        return text.to_string();
+    }
+    let text = PLAIN_MARKERS.iter().fold(text.to_string(), |text, (emoji, marker)| text.replace(emoji, marker));
+    PICTOGRAPH.replace_all(&text, "").into_owned()
+}
+
+const DEFAULT_USER_AGENT: &str = concat!("daily-assistant/", env!("CARGO_PKG_VERSION"));
+
+// Client for all outbound requests, identifying itself with the configured `user_agent`.
+fn http_client(user_agent: Option<&str>) -> Client {
+    Client::builder()
+        .user_agent(user_agent.unwrap_or(DEFAULT_USER_AGENT))
+        .build()
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+async fn call_ai(request: AiRequest) -> AppResult<String> {
+    if offline() {
+        return Ok(offline_ai_reply(&request));
+    }
+    let client = http_client(request.user_agent.as_deref());
+    let url = completions_url(&request.provider, request.base_url.as_deref(), request.completions_path.as_deref());
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "messages": [{"role": "user", "content": request.prompt}],
+        "temperature": request.temperature.unwrap_or(0.7)
+    });
+    if let Some(format) = request.response_format {
+        body["response_format"] = format;
+    }
+    
+    let res = client.post(&url)
+        .headers(ai_headers(&request.api_key, &request.extra_headers)?)
+        .json(&body)
+        .send()
+        .await?;
+        
+    let text = res.text().await?;
+    let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| AppError::Http(e.to_string()))?;
+    if let Some(err) = json.get("error") { return Err(AppError::Http(err.to_string())); }
+    Ok(json["choices"][0]["message"]["content"].as_str().unwrap_or(&text).to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiTestResult {
+    model: String,
+    reply: String,
+    latency_ms: u128,
+}
+
+// Sends a tiny prompt to check the key, base URL and model without running a review.
+async fn test_ai_connection(config: &AppConfig) -> AppResult<AiTestResult> {
+    if offline() {
+        return Err(AppError::Http("offline mode, no request sent".to_string()));
+    }
+    let client = http_client(config.user_agent.as_deref());
+    let url = completions_url(&config.provider, config.base_url.as_deref(), config.completions_path.as_deref());
+    let model = config.effective_model();
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "Reply with OK."}],
+        "max_tokens": 5
+    });
+
+    let started = std::time::Instant::now();
+    let res = client.post(&url)
+        .headers(ai_headers(&config.api_key, &config.ai_extra_headers())?)
+        .json(&body)
+        .send()
+        .await?;
+    let latency_ms = started.elapsed().as_millis();
+
+    let status = res.status();
+    let text = res.text().await?;
+    match status.as_u16() {
+        401 | 403 => return Err(AppError::Unauthorized(format!("API key rejected ({}): {}", status, text))),
+        404 => return Err(AppError::NotFound(format!("Model '{}' or URL {} not found: {}", model, url, text))),
+        _ if !status.is_success() => return Err(AppError::Http(format!("{} - {}", status, text))),
+        _ => {}
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| AppError::Http(e.to_string()))?;
+    Ok(AiTestResult {
+        model: json["model"].as_str().unwrap_or(&model).to_string(),
+        reply: json["choices"][0]["message"]["content"].as_str().unwrap_or_default().trim().to_string(),
+        latency_ms,
+    })
+}
+
+#[tauri::command]
+async fn test_ai() -> AppResult<AiTestResult> {
+    test_ai_connection(&load_config()).await
+}
+
+// Builds the final review prompt. With `chunked_review` enabled and more commits
+// than fit in one batch, each batch is first summarized by its own AI call
+// (map) and the summaries replace the raw commits in the final prompt (reduce).
+async fn build_review_prompt(logs: &[LogItem], commits: &[GitCommit], config: &AppConfig, mode: &str) -> AppResult<String> {
+    let batch_size = config.chunk_batch_size.max(1);
+    if !config.chunked_review || commits.len() <= batch_size {
+        return Ok(generate_prompt_logic(logs, commits, config, mode));
+    }
+
+    // Batches go straight to the AI, so redact here rather than relying on generate_prompt_logic.
+    let (logs, commits) = redact_inputs(logs, commits, config);
+    let total = commits.len().div_ceil(batch_size);
+    let batch_texts: Vec<String> = commits.chunks(batch_size).map(format_commits_for_prompt).collect();
+
+    // Up to `ai_concurrency` batches are summarized at once; every batch runs to completion
+    // even if another fails, and a failed batch falls back to its raw commit list.
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(config.ai_concurrency.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (i, text) in batch_texts.iter().enumerate() {
+        let prompt = format!(
+            "Summarize the following git commits in a few concise bullet points. Keep concrete technical details (what changed and why); if code diffs are provided, use them.\n\n{}",
+            text
+        );
+        let mut req = build_ai_request(config, prompt, "analysis");
+        req.response_format = None;
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            say_err!("🧩 Summarizing commit batch {}/{}...", i + 1, total);
+            (i, call_ai(req).await)
+        });
+    }
+
+    let mut summaries = vec![String::new(); total];
+    while let Some(joined) = tasks.join_next().await {
+        let (i, result) = joined.map_err(|e| AppError::Http(format!("Batch task failed: {}", e)))?;
+        let summary = result.unwrap_or_else(|e| {
+            say_err!("⚠️ Batch {}/{} summary failed ({}), using the raw commits.", i + 1, total, e);
+            batch_texts[i].clone()
+        });
+        summaries[i] = format!("- Batch {}/{}:\n{}", i + 1, total, summary);
+    }
+    Ok(generate_prompt_with_git_text(&logs, &commits, &summaries.join("\n"), &[], &collect_extra_context(config), config, mode))
+}
+
+// Classic async standup: yesterday / today / blockers.
+fn generate_standup_prompt(yesterday: &[LogItem], today: &[LogItem], blockers: &[LogItem], config: &AppConfig) -> String {
+    let (yesterday, _) = redact_inputs(yesterday, &[], config);
+    let (today, _) = redact_inputs(today, &[], config);
+    let (blockers, _) = redact_inputs(blockers, &[], config);
+    let list = |items: Vec<String>| if items.is_empty() { "- (none)".to_string() } else { items.join("\n") };
+    let done = list(yesterday.iter().filter(|l| l.log_type != "blocked").map(|l| format!("- {}{}", l.content, if l.completed { " (done)" } else { "" })).collect());
+    let planned = list(today.iter().filter(|l| l.log_type == "task" && !l.completed).map(|l| format!("- {}", l.content)).collect());
+    let blocked = list(blockers.iter().map(|l| format!("- {}", l.content)).collect());
+    format!(r#"
+        Yesterday's Logs:
+        {}
+
+        Today's Planned Tasks:
+        {}
+
+        Current Blockers:
+        {}
+
+        System Instruction:
+        Write a short async standup update with exactly three sections: "Yesterday" (what got done), "Today" (what's planned), and "Blockers" (write "None" if empty). Use brief bullet points, at most 3 per section.
+    "#, done, planned, blocked)
+}
+
+// Counts for the month-end report, e.g. "84 commits across 3 repos (api: 50, web: 30, ops: 4);
+// 41 notes (12 tasks, 9 completed); active on 17 days".
+fn monthly_stats(logs: &[LogItem], commits: &[GitCommit]) -> String {
+    let mut per_repo: Vec<(&str, usize)> = vec![];
+    for c in commits {
+        let repo = c.repo_name.as_deref().unwrap_or("?");
+        match per_repo.iter_mut().find(|(r, _)| *r == repo) {
+            Some((_, n)) => *n += 1 + c.omitted,
+            None => per_repo.push((repo, 1 + c.omitted)),
+        }
+    }
+    per_repo.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    let total: usize = per_repo.iter().map(|(_, n)| n).sum();
+    let tasks = logs.iter().filter(|l| l.log_type == "task").count();
+    let completed = logs.iter().filter(|l| l.log_type == "task" && l.completed).count();
+    let days = logs.iter().filter_map(|l| l.timestamp.get(..10).map(str::to_string))
+        .chain(commits.iter().filter_map(|c| DateTime::from_timestamp(c.time, 0).map(|t| t.with_timezone(&Local).format("%Y-%m-%d").to_string())))
+        .collect::<std::collections::HashSet<_>>().len();
+    let repos = per_repo.iter().map(|(r, n)| format!("{}: {}", r, n)).collect::<Vec<_>>().join(", ");
+    format!(
+        "{} commits across {} repos{}; {} notes ({} tasks, {} completed); active on {} days",
+        total, per_repo.len(), if repos.is_empty() { String::new() } else { format!(" ({})", repos) }, logs.len(), tasks, completed, days
+    )
+}
+
+// Month-end accomplishments report. A month is too much for one prompt, so each week
+// (days 1-7, 8-14, ...) is summarized by its own AI call first (map), then the weekly
+// summaries and the month's stats are turned into one report (reduce). A failed week
+// falls back to its raw logs and commits.
+async fn generate_monthly_report(logs: &[LogItem], commits: &[GitCommit], config: &AppConfig, start: chrono::NaiveDate, end: chrono::NaiveDate) -> AppResult<String> {
+    let (logs, commits) = redact_inputs(logs, commits, config);
+    let commit_day = |c: &GitCommit| DateTime::from_timestamp(c.time, 0).map(|t| t.with_timezone(&Local).date_naive());
+    let mut weeks = vec![];
+    let mut week_start = start;
+    while week_start <= end {
+        let week_end = (week_start + chrono::Days::new(6)).min(end);
+        let in_week = |d: chrono::NaiveDate| d >= week_start && d <= week_end;
+        let week_logs: Vec<_> = logs.iter()
+            .filter(|l| l.timestamp.get(..10).and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()).is_some_and(in_week))
+            .collect();
+        let week_commits: Vec<_> = commits.iter().filter(|&c| commit_day(c).is_some_and(in_week)).collect();
+        if !week_logs.is_empty() || !week_commits.is_empty() {
+            let logs_text = week_logs.iter().enumerate().map(|(i, l)| format_log_line(&config.log_line_format, i + 1, l)).collect::<Vec<_>>().join("\n");
+            weeks.push((format!("{} – {}", week_start.format("%m-%d"), week_end.format("%m-%d")), format!("Manual Logs:\n{}\n\nGit Commits:\n{}", logs_text, format_commits_for_prompt(week_commits))));
+        }
+        week_start = week_end + chrono::Days::new(1);
+    }
+    if weeks.is_empty() {
+        return Err(AppError::NotFound(format!("No logs or commits between {} and {}.", start, end)));
+    }
+
+    let mut summaries = vec![];
+    for (i, (range, text)) in weeks.iter().enumerate() {
+        say_err!("🧩 Summarizing week {}/{} ({})...", i + 1, weeks.len(), range);
+        let prompt = format!(
+            "Summarize one week of work ({}) in at most 6 concise bullet points: deliverables shipped, significant progress and problems solved. Skip routine detail.\n\n{}",
+            range, text
+        );
+        let mut req = build_ai_request(config, prompt, "analysis");
+        req.response_format = None;
+        let summary = call_ai(req).await.unwrap_or_else(|e| {
+            say_err!("⚠️ Week {} summary failed ({}), using the raw entries.", range, e);
+            text.clone()
+        });
+        summaries.push(format!("- Week {}:\n{}", range, summary));
+    }
+
+    let prompt = format!(r#"
+        Month: {}
+        Stats: {}
+
+        Weekly Summaries:
+        {}
+
+        System Instruction:
+        Write a month-end accomplishments summary suitable for a performance review. Group the work into 3-5 themes, lead with the major deliverables and their impact, and leave out day-to-day detail. Quote the stats line once. Finish with notable problems solved and open items carried into next month.
+    "#, start.format("%Y-%m"), monthly_stats(&logs, &commits), summaries.join("\n"));
+    let mut req = build_ai_request(config, prompt, "analysis");
+    req.response_format = None;
+    call_ai(req).await
+}
+
+fn generate_compare_prompt(from: &str, old: &str, to: &str, new: &str) -> String {
+    format!(r#"
+        Report of {}:
+        {}
+
+        Report of {}:
+        {}
+
+        System Instruction:
+        Compare the two daily reports above. Write three short sections: "Progressed" (work that moved forward or was finished), "Stalled" (items or problems that appear in both without visible progress), and "New" (work or problems that only appear in the later report). Use brief bullet points and write "None" for an empty section.
+    "#, from, old, to, new)
+}
+
+// Line diff via longest common subsequence: "  " unchanged, "- " only in `old`, "+ " only in `new`.
+fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    // lcs[i][j] = length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j, mut out) = (0, 0, vec![]);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(format!("  {}", a[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push(format!("+ {}", b[j]));
+            j += 1;
+        } else {
+            out.push(format!("- {}", a[i]));
+            i += 1;
+        }
+    }
+    out
+}
+
+// Narrows a review to manual logs ("logs") or commits ("git"); "both" keeps everything.
+// Dropping the repos means git isn't scanned at all.
+fn apply_review_source(source: &str, logs: &mut Vec<LogItem>, config: &mut AppConfig) {
+    match source {
+        "logs" => config.git_paths.clear(),
+        "git" => logs.clear(),
+        _ => {}
+    }
+}
+
+// Scans today's commits and asks the AI for a review ("analysis") or a report ("export").
+// `date` replays an earlier day's commits instead; `until` cuts them off earlier than the end of the day.
+async fn generate_review(logs: &[LogItem], config: &AppConfig, mode: &str, date: Option<chrono::NaiveDate>, until: Option<&str>) -> AppResult<String> {
+    let commits = scan_git_repos_on(config.git_paths.clone(), config.deep_analysis, date, until).unwrap_or_default();
+    let prompt = build_review_prompt(logs, &commits, config, mode).await?;
+    let raw = call_ai(build_ai_request(config, prompt, mode)).await?;
+    render_ai_reply(raw, config, mode)
+}
+
+#[tauri::command]
+async fn generate_structured_report(state: State<'_, DbState>, mode: String) -> AppResult<StructuredReport> {
+    let config = AppConfig { structured_output: true, ..load_config() };
+    let logs = {
+        let conn = state.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))?;
+        query_today_logs(&conn)?
+    };
+    let commits = scan_git_repos(config.git_paths.clone(), config.deep_analysis, None).unwrap_or_default();
+    let prompt = build_review_prompt(&logs, &commits, &config, &mode).await?;
+    let raw = call_ai(build_ai_request(&config, prompt, &mode)).await?;
+    parse_structured_report(&raw)
+}
+
+
+
+#[derive(Parser)]
+#[command(name = "Daily Assistant", version, about = "A Daily Review Helper")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+    /// Make no network calls: AI replies are stubbed and nothing is delivered
+    #[arg(long, global = true)]
+    offline: bool,
+    /// ASCII-only output: emoji become markers like [ok] / [error] (also NO_EMOJI or NO_COLOR)
+    #[arg(long, global = true)]
+    plain: bool,
+    /// Hide progress messages (deep git scans)
+    #[arg(long, global = true)]
+    quiet: bool,
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed once at startup; the Config flag list is just big
+enum Commands {
+    /// Add a new log entry (Use --stdin or `-` to add one note per line from stdin)
+    Add { 
+        /// The content of the log/note
+        content: Option<String>,
+        /// Read notes from stdin, one per non-empty line
+        #[arg(long)]
+        stdin: bool,
+        /// Write a multi-line note in $EDITOR
+        #[arg(long, conflicts_with_all = ["content", "stdin"])]
+        editor: bool,
+        /// Log type: note, task, problem, or blocked (waiting on someone else)
+        #[arg(long = "type", default_value = "note", value_parser = LOG_TYPES)]
+        log_type: String,
+    },
+    /// List today's logs
+    List {
+        /// Print sections per log type (tasks, notes, problems, blocked) instead of one stream
+        #[arg(long)]
+        grouped: bool,
+    },
+    /// Show the most recent N logs across all dates
+    Last {
+        /// Number of logs to show (max 200)
+        #[arg(default_value_t = 10)]
+        n: i64,
+    },
+    /// Mark a task as completed (use --undo to reopen it)
+    Done {
+        /// The ID of the task
+        id: i64,
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Delete a log by ID
+    Del { 
+        /// The ID of the log to delete
+        id: i64 
+    },
+    /// Permanently delete logs older than the given number of days
+    Purge {
+        /// Delete logs older than this many days
+        #[arg(long)]
+        days: i64,
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        force: bool,
+    },
+    /// Merge logs from another journal database, skipping duplicates
+    Merge {
+        /// Path to the other SQLite database file
+        path: String,
+    },
+    /// Search all logs with a regex (e.g. "JIRA-\d+")
+    Search {
+        pattern: String,
+    },
+    /// Rank commit authors across all configured repos
+    Contributors {
+        /// Only count commits since this date (any format git accepts)
+        #[arg(long, default_value = "1 week ago")]
+        since: String,
+    },
+    /// Back up the database now (see `config --auto-backup` for daily backups)
+    Backup {
+        /// Directory to write the backup to instead of the configured backup directory
+        #[arg(long)]
+        dir: Option<std::path::PathBuf>,
+    },
+    /// Replace the database with a backup; the current database is backed up first
+    Restore {
+        /// Backup file to restore
+        #[arg(long)]
+        from: std::path::PathBuf,
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show database statistics
+    DbStats,
+    /// List the #tags used in logs, most used first
+    Tags,
+    /// Write today's logs, commits and generated prompt to a JSON file, without any network call
+    ExportBundle {
+        /// Bundle file to write
+        #[arg(long, default_value = "bundle.json")]
+        out: std::path::PathBuf,
+        /// Prompt to generate
+        #[arg(long, default_value = "analysis", value_parser = ["analysis", "export", "plan", "summary"])]
+        mode: String,
+        /// What to include: manual logs, git commits, or both
+        #[arg(long, default_value = "both", value_parser = ["logs", "git", "both"])]
+        source: String,
+    },
+    /// Estimate today's active work time from commit timestamps
+    EstimateTime,
+    /// Compare AI latency of the main endpoint and every --add-profile endpoint
+    Bench {
+        /// Seconds to wait for each endpoint
+        #[arg(long, default_value_t = 20)]
+        timeout: u64,
+    },
+    /// Print the full diff stored with a report for a commit (hash prefix)
+    Diff {
+        hash: String,
+    },
+    /// Compare two days' stored reports: what progressed, what stalled
+    CompareReports {
+        /// Earlier date (YYYY-MM-DD); defaults to the day before --to
+        #[arg(long)]
+        from: Option<chrono::NaiveDate>,
+        /// Later date (YYYY-MM-DD); defaults to today
+        #[arg(long)]
+        to: Option<chrono::NaiveDate>,
+        /// Print a line diff instead of asking the AI
+        #[arg(long)]
+        text: bool,
+        /// Compare the reports of this schedules.toml entry (needed when several have reports that day)
+        #[arg(long)]
+        schedule: Option<String>,
+    },
+    /// Configure settings (Set --api-key, --add-repo, --deep-analysis, or --show)
+    Config {
+        /// Print the effective config as JSON with secrets masked
+        #[arg(long)]
+        show: bool,
+        /// Print the config file and database paths
+        #[arg(long)]
+        path: bool,
+        /// Open the data directory in the system file manager
+        #[arg(long)]
+        open: bool,
+        /// List configured schedules and when each fires next
+        #[arg(long)]
+        schedules: bool,
+        /// List delivery sinks, whether each is enabled and which required settings are missing
+        #[arg(long)]
+        sinks: bool,
+        /// Remove repos listed more than once under different spellings (symlinks, trailing slashes...)
+        #[arg(long)]
+        dedupe_repos: bool,
+        /// Reset every setting (including the repo list) to its default; keys, credentials, AI headers and webhooks are kept
+        #[arg(long)]
+        reset: bool,
+        /// Reset a single setting to its default (config.json field name, e.g. report_template)
+        #[arg(long)]
+        reset_field: Option<String>,
+        /// With --reset, reset the credentials too
+        #[arg(long, requires = "reset")]
+        include_secrets: bool,
+        /// Skip the --reset confirmation prompt
+        #[arg(long)]
+        force: bool,
+        /// Check the report template (and schedules.toml templates) for mistakes
+        #[arg(long)]
+        lint_template: bool,
+        /// Set your OpenAI (or compatible) API Key, or file:PATH to read it from a file at runtime
+        #[arg(long)]
+        api_key: Option<String>,
+        /// Add a new Git repository path
+        #[arg(long)]
+        add_repo: Option<String>,
+        /// Set a display name for a repository (PATH=NAME)
+        #[arg(long)]
+        repo_name: Option<String>,
+        /// Enable or disable Deep Git Analysis (fetching code diffs)
+        #[arg(long)]
+        deep_analysis: Option<bool>,
+        /// Set Feishu App ID
+        #[arg(long)]
+        feishu_app_id: Option<String>,
+        /// Set Feishu App Secret
+        #[arg(long)]
+        feishu_app_secret: Option<String>,
+        /// Set Feishu recipient emails (comma-separated)
+        #[arg(long)]
+        feishu_target: Option<String>,
+        /// Set Schedule Time (HH:MM)
+        #[arg(long)]
+        schedule: Option<String>,
+        /// Only include commits made after this time (HH:MM, empty to clear)
+        #[arg(long)]
+        work_hours_start: Option<String>,
+        /// Only include commits made before this time (HH:MM, empty to clear)
+        #[arg(long)]
+        work_hours_end: Option<String>,
+        /// Set the shared secret required by the /trigger-review webhook
+        #[arg(long)]
+        webhook_token: Option<String>,
+        /// Set the log/commit similarity threshold for duplicate detection (0.0-1.0, 0 disables)
+        #[arg(long)]
+        dedupe_threshold: Option<f32>,
+        /// Request structured (JSON schema) reports from the AI
+        #[arg(long)]
+        structured_output: Option<bool>,
+        /// Include commit message bodies in the prompt
+        #[arg(long)]
+        include_commit_body: Option<bool>,
+        /// Set the AI temperature used for analysis reviews
+        #[arg(long)]
+        analysis_temperature: Option<f32>,
+        /// Set the AI temperature used for exported reports
+        #[arg(long)]
+        export_temperature: Option<f32>,
+        /// Summarize commits in batches before the final review (for very large days)
+        #[arg(long)]
+        chunked_review: Option<bool>,
+        /// Number of commits per batch in chunked reviews
+        #[arg(long)]
+        chunk_batch_size: Option<usize>,
+        /// Add or replace a named prompt snippet (NAME=TEXT)
+        #[arg(long)]
+        add_snippet: Option<String>,
+        /// Toggle whether a prompt snippet is active for every review
+        #[arg(long)]
+        toggle_snippet: Option<String>,
+        /// Keep at most N commits per repo (0 for no limit)
+        #[arg(long)]
+        max_commits_per_repo: Option<usize>,
+        /// Order commits in the prompt: by_repo or chronological
+        #[arg(long, value_parser = ["by_repo", "chronological"])]
+        commit_order: Option<String>,
+        /// Alert when the scheduled report fails
+        #[arg(long)]
+        alert_on_failure: Option<bool>,
+        /// Webhook that receives failure alerts as {"text": "..."} (desktop notification when unset)
+        #[arg(long)]
+        alert_webhook: Option<String>,
+        /// Show a desktop notification when the scheduled report is sent
+        #[arg(long)]
+        notify_on_success: Option<bool>,
+        /// Scan all branches and note each commit's branch in the prompt
+        #[arg(long)]
+        all_branches: Option<bool>,
+        /// Set how logs are listed in the prompt ({n}, {time}, {type}, {content})
+        #[arg(long)]
+        log_line_format: Option<String>,
+        /// Use the AI for hook-summary notes (raw commit subjects when false)
+        #[arg(long)]
+        hook_ai_summary: Option<bool>,
+        /// Set the chrono format for log times in list output (e.g. "%H:%M:%S")
+        #[arg(long)]
+        time_format: Option<String>,
+        /// Load custom rules from this file at review time ("" to use the inline rules again)
+        #[arg(long)]
+        rules_file: Option<String>,
+        /// Only fetch diffs in deep analysis when there are fewer than N commits (0 to always fetch)
+        #[arg(long)]
+        auto_deep_threshold: Option<usize>,
+        /// Number of AI requests chunked reviews may run in parallel
+        #[arg(long)]
+        ai_concurrency: Option<usize>,
+        /// Keep full diffs alongside stored reports (the AI still sees truncated ones)
+        #[arg(long)]
+        keep_full_diffs: Option<bool>,
+        /// Merge an author's alternate name in Contributors (ALIAS=NAME)
+        #[arg(long)]
+        author_alias: Option<String>,
+        /// Set the chat completions path appended to the base URL ("" for the default /chat/completions)
+        #[arg(long)]
+        completions_path: Option<String>,
+        /// Group commits and notes by ticket ids matching this regex, e.g. "[A-Z]+-\d+" ("" to disable)
+        #[arg(long)]
+        ticket_regex: Option<String>,
+        /// Only fire the schedule on these days, e.g. mon,tue,wed,thu,fri
+        #[arg(long)]
+        weekdays: Option<String>,
+        /// Set the AI provider; openai, deepseek and moonshot also fill in their base URL and default model
+        #[arg(long)]
+        provider: Option<String>,
+        /// Set the model name
+        #[arg(long)]
+        model: Option<String>,
+        /// Set the default model for a provider (PROVIDER=MODEL), used when switching to it
+        #[arg(long)]
+        provider_model: Option<String>,
+        /// Add or replace an endpoint for Bench and --model-preset (NAME=PROVIDER,KEY[,MODEL]; PROVIDER may be a base URL, an empty KEY uses the main one)
+        #[arg(long)]
+        add_profile: Option<String>,
+        /// Model preset used by default for analysis reviews ("" for the main model)
+        #[arg(long)]
+        analysis_preset: Option<String>,
+        /// Model preset used by default for export reports ("" for the main model)
+        #[arg(long)]
+        export_preset: Option<String>,
+        /// Minutes between commits that start a new session in time estimates
+        #[arg(long)]
+        time_gap: Option<i64>,
+        /// Add the commit-based time estimate to review prompts
+        #[arg(long)]
+        include_time_estimate: Option<bool>,
+        /// Rough prompt token budget (0 for no limit)
+        #[arg(long)]
+        max_prompt_tokens: Option<usize>,
+        /// What to drop first when the prompt is over budget
+        #[arg(long, value_parser = ["oldest_diffs", "all_diffs", "oldest_commits"])]
+        truncation_strategy: Option<String>,
+        /// Include uncommitted changes and stashes in review prompts as work in progress
+        #[arg(long)]
+        include_wip: Option<bool>,
+        /// Retries for git commands that fail on a lock held by another git process (0 to disable)
+        #[arg(long)]
+        git_retries: Option<usize>,
+        /// Footer appended to delivered reports ({date}, {commits}, {logs}, {link}; "" to omit)
+        #[arg(long)]
+        delivery_footer: Option<String>,
+        /// URL or file path the footer's {link} points to ({date} is filled in; "" to clear)
+        #[arg(long)]
+        report_link: Option<String>,
+        /// Greeting put before delivered reports, e.g. "Hi team," ({date} is filled in; "" to clear)
+        #[arg(long)]
+        report_prefix: Option<String>,
+        /// Sign-off put after delivered reports, before the footer ({date} is filled in; "" to clear)
+        #[arg(long)]
+        report_suffix: Option<String>,
+        /// Deliver reports as an attached .md file rather than a text message (Feishu)
+        #[arg(long)]
+        delivery_as_attachment: Option<bool>,
+        /// Back up the database once a day while the daemon runs
+        #[arg(long)]
+        auto_backup: Option<bool>,
+        /// Directory for database backups ("" for "backups" next to config.json)
+        #[arg(long)]
+        backup_dir: Option<String>,
+        /// Number of backups to keep
+        #[arg(long)]
+        backup_keep: Option<usize>,
+        /// Project every report leads with, matched against repo names and #tags in logs ("" to clear)
+        #[arg(long)]
+        focus_project: Option<String>,
+        /// User-Agent sent with outbound requests ("" for the default daily-assistant/<version>)
+        #[arg(long)]
+        user_agent: Option<String>,
+        /// Add a regex whose matches are redacted before anything is sent to the AI
+        #[arg(long)]
+        add_redaction: Option<String>,
+        /// Remove all redaction patterns, including the defaults
+        #[arg(long)]
+        clear_redactions: bool,
+        /// Truncate notes longer than this many characters when saving (0 for no limit)
+        #[arg(long)]
+        max_log_length: Option<usize>,
+        /// Add a shell command whose output is included in review prompts
+        #[arg(long)]
+        add_context_command: Option<String>,
+        /// Remove all context commands
+        #[arg(long)]
+        clear_context_commands: bool,
+        /// Open the report with a line of activity metrics
+        #[arg(long)]
+        include_metrics: Option<bool>,
+        /// Add a header to every AI request (NAME=VALUE)
+        #[arg(long)]
+        ai_header: Option<String>,
+        /// OpenAI organization id sent as OpenAI-Organization with the openai provider ("" to clear)
+        #[arg(long)]
+        openai_org: Option<String>,
+        /// OpenAI project id sent as OpenAI-Project with the openai provider ("" to clear)
+        #[arg(long)]
+        openai_project: Option<String>,
+        /// Only include commits by this author (overrides auto-detection)
+        #[arg(long)]
+        git_author: Option<String>,
+        /// Detect the author from each repo's git config when --git-author is unset
+        #[arg(long)]
+        detect_git_author: Option<bool>,
+        /// Set the time (HH:MM) before which tasks count as the morning plan
+        #[arg(long)]
+        plan_cutoff: Option<String>,
+        /// Skip scheduled reports unless logs or commits reach this count
+        #[arg(long)]
+        min_activity: Option<usize>,
+    },
+    /// Sync Git repositories (Use --deep to include diffs)
+    Sync {
+        /// Force a deep sync (fetch code diffs) regardless of config
+        #[arg(long)]
+        deep: bool,
+        /// Only scan these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        repos: Vec<String>,
+        /// Skip these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        exclude_repos: Vec<String>,
+        /// Only include commits up to this time (HH:MM, YYYY-MM-DD or YYYY-MM-DD HH:MM)
+        #[arg(long)]
+        until: Option<String>,
+    },
+    /// List commits grouped by repo, without diffs or AI
+    Commits {
+        /// Show commits since this time (any format git accepts)
+        #[arg(long, default_value = "midnight")]
+        since: String,
+        /// Only scan these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        repos: Vec<String>,
+        /// Skip these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        exclude_repos: Vec<String>,
+    },
+    /// Generate AI Review (Default) or Export Report (Use --export)
+    Review {
+        /// Generate a formal report based on template instead of analysis
         #[arg(long)]
         export: bool,
+        /// Compare the morning's planned tasks against what was actually done
+        #[arg(long, conflicts_with = "export")]
+        plan: bool,
+        /// Only a one-sentence TL;DR of the day
+        #[arg(long, conflicts_with_all = ["export", "plan"])]
+        summary: bool,
+        /// Also write the result to this file (appended under a timestamped header; .html files get rendered HTML)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+        /// Output the result as JSON (the --out file is then overwritten)
+        #[arg(long)]
+        json: bool,
+        /// Include a named prompt snippet for this review (repeatable)
+        #[arg(long = "snippet")]
+        snippets: Vec<String>,
+        /// Only scan these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        repos: Vec<String>,
+        /// Skip these repos (comma-separated names or paths)
+        #[arg(long, value_delimiter = ',')]
+        exclude_repos: Vec<String>,
+        /// Use this model preset (see `config --add-profile`) instead of the configured model
+        #[arg(long)]
+        model_preset: Option<String>,
+        /// Lead with this project (repo name or #tag) and keep the rest brief
+        #[arg(long)]
+        focus: Option<String>,
+        /// Regenerate the review for an earlier day (YYYY-MM-DD) from its logs and commits
+        #[arg(long)]
+        date: Option<chrono::NaiveDate>,
+        /// What to review: manual logs, git commits, or both
+        #[arg(long, default_value = "both", value_parser = ["logs", "git", "both"])]
+        source: String,
+        /// Only include commits up to this time (HH:MM on the reviewed day, YYYY-MM-DD or YYYY-MM-DD HH:MM)
+        #[arg(long)]
+        until: Option<String>,
+    },
+    /// Summarize a calendar month's accomplishments (themes and major deliverables) for performance reviews
+    Monthly {
+        /// Month to report on (YYYY-MM); defaults to the current month
+        #[arg(long)]
+        month: Option<String>,
+        /// Also write the report to this file (appended under a timestamped header; .html files get rendered HTML)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Generate a short standup update (yesterday / today / blockers)
+    Standup,
+    /// Log a one-line summary of new commits (for git hooks, e.g. post-commit: `da hook-summary`)
+    HookSummary {
+        /// Summarize commits after this revision (defaults to $DA_PREV_HEAD, else only the latest commit)
+        #[arg(long)]
+        from: Option<String>,
+        /// Repository path
+        #[arg(long, default_value = ".")]
+        repo: String,
+    },
+    /// Send your own text through the delivery sinks, skipping the AI
+    Send {
+        /// Text to send
+        #[arg(long, conflicts_with = "file")]
+        text: Option<String>,
+        /// Read the text to send from a file
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
     },
     /// Manage the application service (Status, Start, Stop)
     Service {
         #[command(subcommand)]
         action: ServiceCommands,
     },
+    /// Serve a local HTTP API for editors and scripts
+    Serve {
+        /// Address to bind to (localhost only by default)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on
+        #[arg(long, default_value_t = 8765)]
+        port: u16,
+    },
     /// Internal: Run as a background daemon (do not use directly)
     Daemon,
 }
@@ -402,6 +3084,61 @@ enum ServiceCommands {
     Start,
     /// Stop the service
     Stop,
+    /// Send a tiny prompt to verify the AI key, base URL and model
+    TestAi,
+    /// Run the whole scheduled pipeline (prompt, AI, delivery) on sample data; nothing is saved
+    TestRun,
+    /// Skip scheduled reports until a date (YYYY-MM-DD)
+    Pause {
+        #[arg(long)]
+        until: chrono::NaiveDate,
+    },
+    /// Resume scheduled reports after a pause
+    Resume,
+}
+
+fn open_in_file_manager(dir: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+    Command::new(program).arg(dir).spawn().map(|_| ())
+}
+
+// Markdown report as a standalone HTML document. Styles are inline since many email
+// clients drop <style> blocks.
+fn render_markdown_to_html(md: &str) -> String {
+    use pulldown_cmark::{html, Options, Parser};
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(md, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS));
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"></head>\n<body style=\"margin:0;padding:24px;background:#f6f7f9;\">\n<div style=\"max-width:720px;margin:0 auto;padding:24px 32px;background:#fff;border-radius:8px;font-family:-apple-system,'Segoe UI','PingFang SC','Microsoft YaHei',sans-serif;font-size:15px;line-height:1.6;color:#1f2328;\">\n{}</div>\n</body></html>\n",
+        body
+    )
+}
+
+#[tauri::command]
+fn render_report_html(markdown: String) -> String {
+    render_markdown_to_html(&markdown)
+}
+
+// Markdown output is appended under a timestamped header so a single file can
+// act as a running journal; JSON output replaces the file to keep it valid.
+// `.html` targets get the rendered report (overwritten).
+fn write_report_file(path: &std::path::Path, content: &str, json: bool) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    if json {
+        return std::fs::write(path, content);
+    }
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm")) {
+        return std::fs::write(path, render_markdown_to_html(content));
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    std::io::Write::write_all(&mut file, format!("\n## {}\n\n{}\n", Local::now().format("%Y-%m-%d %H:%M"), content).as_bytes())
 }
 
 fn get_db_path() -> std::path::PathBuf {
@@ -425,99 +3162,935 @@ fn get_db_path() -> std::path::PathBuf {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let cli = Cli::parse();
+    let env_set = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+    if cli.plain || env_set("NO_EMOJI") || env_set("NO_COLOR") {
+        PLAIN.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    if cli.quiet {
+        QUIET.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    if cli.offline {
+        OFFLINE.store(true, std::sync::atomic::Ordering::Relaxed);
+        say_err!("📴 Offline mode: AI replies are stubbed and nothing is sent.");
+    }
 
     if let Some(cmd) = cli.command {
         // HEADLESS CLI EXECUTION
         let db_path = get_db_path();
         let db_state = DbState::init(db_path).expect("Failed to initialize database");
-        let conn = db_state.conn.lock().unwrap();
+        let mut conn = db_state.conn.lock().unwrap();
 
         match cmd {
-            Commands::Add { content } => {
-                conn.execute("INSERT INTO logs (content, log_type, timestamp) VALUES (?1, ?2, datetime('now', 'localtime'))", [&content, &"note".to_string()]).unwrap();
-                println!("✅ Note added: {}", content);
+            Commands::Serve { host, port } => {
+                drop(conn);
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                if let Err(e) = rt.block_on(server::serve(&host, port)) {
+                    say!("❌ Server Failed: {}", e);
+                }
+            },
+            Commands::Add { content, stdin, editor, log_type } => {
+                let config = load_config();
+                let limit = |content: &str| {
+                    let (content, truncated) = limit_log_length(content, &config);
+                    if let Some(len) = truncated {
+                        say!("⚠️ Note truncated: {} characters is over the {} limit.", len, config.max_log_length.unwrap_or_default());
+                    }
+                    content
+                };
+                if editor {
+                    match edit_in_editor() {
+                        Ok(note) if note.is_empty() => println!("Empty note, nothing added."),
+                        Ok(note) => {
+                            let note = limit(&note);
+                            insert_log(&conn, &note, &log_type).unwrap();
+                            say!("✅ Note added ({} lines).", note.lines().count());
+                        },
+                        Err(e) => say!("❌ Editor failed: {}", e),
+                    }
+                } else if stdin || content.as_deref() == Some("-") {
+                    let mut input = String::new();
+                    std::io::Read::read_to_string(&mut std::io::stdin(), &mut input).expect("Failed to read stdin");
+                    let tx = conn.transaction().unwrap();
+                    let mut count = 0;
+                    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                        tx.execute("INSERT INTO logs (content, log_type, timestamp) VALUES (?1, ?2, datetime('now', 'localtime'))", [limit(line).as_str(), log_type.as_str()]).unwrap();
+                        count += 1;
+                    }
+                    tx.commit().unwrap();
+                    say!("✅ {} notes added.", count);
+                } else if let Some(content) = content {
+                    let content = limit(&content);
+                    insert_log(&conn, &content, &log_type).unwrap();
+                    if log_type == "blocked" { say!("⛔ Blocked item added: {}", content); } else { say!("✅ Note added: {}", content); }
+                } else {
+                    say!("❌ Provide the note content, or use --stdin to read notes from stdin.");
+                }
+            },
+            Commands::List { grouped: true } => {
+                let mut logs = query_today_logs(&conn).unwrap();
+                logs.reverse();
+                let time_format = load_config().time_display_format;
+                say!("📅 Today's Notes:");
+                for (log_type, header) in LOG_TYPES.iter().zip(["☑️ Tasks", "📝 Notes", "⚠️ Problems", "⛔ Blocked"]) {
+                    let group: Vec<_> = logs.iter().filter(|l| l.log_type == *log_type).collect();
+                    if group.is_empty() { continue; }
+                    say!("\n{} ({})", header, group.len());
+                    for log in group {
+                        println!("[{}] {}  {}", log.id.unwrap_or_default(), format_timestamp(&log.timestamp, &time_format), log.content);
+                    }
+                }
             },
-            Commands::List => {
+            Commands::List { .. } => {
                 let mut stmt = conn.prepare("SELECT id, timestamp, content FROM logs WHERE date(timestamp) = date('now', 'localtime') ORDER BY id ASC").unwrap();
                 let logs = stmt.query_map([], |row| Ok((row.get::<_,i64>(0)?, row.get::<_,String>(1)?, row.get::<_,String>(2)?))).unwrap();
-                println!("📅 Today's Notes:");
-                for log in logs { if let Ok((id, ts, content)) = log { println!("[{}] {}  {}", id, &ts.split_whitespace().nth(1).unwrap_or(&ts)[0..5], content); } }
+                let time_format = load_config().time_display_format;
+                say!("📅 Today's Notes:");
+                for log in logs { if let Ok((id, ts, content)) = log { println!("[{}] {}  {}", id, format_timestamp(&ts, &time_format), content); } }
+            },
+            Commands::Last { n } => {
+                let logs = query_recent_logs(&conn, n).unwrap();
+                let time_format = format!("%Y-%m-%d {}", load_config().time_display_format);
+                say!("🕘 Last {} Notes:", logs.len());
+                for log in &logs {
+                    println!("[{}] {}  {}", log.id.unwrap_or_default(), format_timestamp(&log.timestamp, &time_format), log.content);
+                }
+            },
+            Commands::Done { id, undo } => {
+                match set_log_completed(&conn, id, !undo) {
+                    Ok(()) => say!("{} ID {} marked as {}.", if undo { "↩️" } else { "☑️" }, id, if undo { "open" } else { "done" }),
+                    Err(e) => say!("❌ {}", e),
+                }
             },
             Commands::Del { id } => {
-                 if conn.execute("DELETE FROM logs WHERE id = ?1", [&id]).unwrap() > 0 { println!("🗑️ Deleted note ID: {}", id); } 
-                 else { println!("❌ Note ID {} not found.", id); }
+                 if conn.execute("DELETE FROM logs WHERE id = ?1", [&id]).unwrap() > 0 { say!("🗑️ Deleted note ID: {}", id); } 
+                 else { say!("❌ Note ID {} not found.", id); }
+            },
+            Commands::Purge { days, force } => {
+                if days <= 0 {
+                    say!("❌ Purge age must be at least 1 day.");
+                    return;
+                }
+                let count = match count_logs_older_than(&conn, days) {
+                    Ok(count) => count,
+                    Err(e) => {
+                        say!("❌ Purge Failed: {}", e);
+                        return;
+                    }
+                };
+                if count == 0 {
+                    println!("Nothing to purge: no logs older than {} days.", days);
+                } else {
+                    let confirmed = force || {
+                        print!("{}", styled(&format!("⚠️ Permanently delete {} logs older than {} days? [y/N] ", count, days)));
+                        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+                        let mut answer = String::new();
+                        std::io::stdin().read_line(&mut answer).unwrap();
+                        answer.trim().eq_ignore_ascii_case("y")
+                    };
+                    if confirmed {
+                        match purge_logs_older_than(&mut conn, days) {
+                            Ok(deleted) => say!("🗑️ Purged {} logs.", deleted),
+                            Err(e) => say!("❌ Purge Failed: {}", e),
+                        }
+                    } else {
+                        println!("Cancelled.");
+                    }
+                }
             },
-            Commands::Config { api_key, add_repo, deep_analysis, feishu_app_id, feishu_app_secret, feishu_target, schedule } => {
+            Commands::Merge { path } => {
+                match merge_logs_from(&mut conn, &path) {
+                    Ok(report) => say!("🔀 Merged {}: {} inserted, {} skipped.", path, report.inserted, report.skipped),
+                    Err(e) => say!("❌ Merge Failed: {}", e),
+                }
+            },
+            Commands::Diff { hash } => {
+                match load_full_diff(&conn, &hash) {
+                    Ok(Some((hash, diff))) => println!("commit {}\n\n{}", hash, diff),
+                    Ok(None) => say!("❌ No stored diff for {} (only diffs truncated in a stored report are kept).", hash),
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::CompareReports { from, to, text, schedule } => {
+                let to = to.unwrap_or_else(|| Local::now().date_naive());
+                let from = from.unwrap_or_else(|| to - chrono::Days::new(1));
+                let mut reports = vec![];
+                for date in [from, to] {
+                    let date = date.format("%Y-%m-%d").to_string();
+                    // Without --schedule, fall back to the only schedules.toml report of the day
+                    let key = match &schedule {
+                        Some(name) => format!("{}#{}", date, name),
+                        None if matches!(load_report(&conn, &date), Ok(Some(_))) => date.clone(),
+                        None => match scheduled_report_names(&conn, &date) {
+                            Ok(names) if names.len() == 1 => format!("{}#{}", date, names[0]),
+                            Ok(names) if names.len() > 1 => {
+                                say!("❌ Several scheduled reports for {}; pick one with --schedule ({}).", date, names.join(", "));
+                                continue;
+                            }
+                            _ => date.clone(),
+                        },
+                    };
+                    match load_report(&conn, &key) {
+                        Ok(Some(report)) => reports.push((date, report.content)),
+                        Ok(None) => say!("❌ No stored report for {} (reports are stored by the scheduled job).", date),
+                        Err(e) => say!("❌ {}", e),
+                    }
+                }
+                let Ok([(from, old), (to, new)]) = <[_; 2]>::try_from(reports) else { return };
+                if text {
+                    say!("📊 {} → {}\n", from, to);
+                    for line in diff_lines(&old, &new) {
+                        println!("{}", line);
+                    }
+                    return;
+                }
+                say!("📊 Comparing {} → {}...", from, to);
+                let config = load_config();
+                let mut req = build_ai_request(&config, generate_compare_prompt(&from, &old, &to, &new), "analysis");
+                req.response_format = None;
+                let rt = Runtime::new().unwrap();
+                match rt.block_on(call_ai(req)) {
+                    Ok(res) => println!("\n{}", res),
+                    Err(e) => say!("❌ AI Error: {}", e),
+                }
+            },
+            Commands::Search { pattern } => {
+                match search_logs_matching(&conn, &pattern) {
+                    Ok(logs) => {
+                        let time_format = format!("%Y-%m-%d {}", load_config().time_display_format);
+                        say!("🔍 {} match{}{}", logs.len(), if logs.len() == 1 { "" } else { "es" }, if logs.len() == MAX_SEARCH_RESULTS { " (limit reached)" } else { "" });
+                        for log in &logs {
+                            println!("[{}] {}  {}", log.id.unwrap_or_default(), format_timestamp(&log.timestamp, &time_format), log.content);
+                        }
+                    },
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Contributors { since } => {
+                let ranked = count_contributors(&load_config(), &since);
+                if ranked.is_empty() {
+                    println!("No commits since {}.", since);
+                    return;
+                }
+                let width = ranked.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+                say!("👥 Commits since {}:", since);
+                for (i, (name, count)) in ranked.iter().enumerate() {
+                    println!("{:>3}. {:<width$}  {}", i + 1, name, count, width = width);
+                }
+            },
+            Commands::Bench { timeout } => {
+                let config = load_config();
+                let mut endpoints = vec![("main".to_string(), config.clone())];
+                for p in &config.ai_profiles {
+                    endpoints.push((p.name.clone(), config.with_profile(&p.name).unwrap()));
+                }
+                say!("⏱️ Benchmarking {} endpoint{}...", endpoints.len(), if endpoints.len() == 1 { "" } else { "s" });
+                let rt = Runtime::new().unwrap();
+                let mut results = rt.block_on(async {
+                    let mut results = vec![];
+                    for (name, cfg) in &endpoints {
+                        let result = match tokio::time::timeout(std::time::Duration::from_secs(timeout), test_ai_connection(cfg)).await {
+                            Ok(r) => r,
+                            Err(_) => Err(AppError::Http(format!("timed out after {}s", timeout))),
+                        };
+                        results.push((name.clone(), cfg.model.clone(), result));
+                    }
+                    results
+                });
+                // Fastest first, failures last
+                results.sort_by_key(|(_, _, r)| r.as_ref().map(|t| t.latency_ms).unwrap_or(u128::MAX));
+                let width = results.iter().map(|(name, model, _)| name.len() + model.len() + 3).max().unwrap_or(0);
+                for (name, model, result) in &results {
+                    let label = format!("{} ({})", name, model);
+                    match result {
+                        Ok(r) => say!("✅ {:<width$}  {} ms", label, r.latency_ms, width = width),
+                        Err(e) => say!("❌ {:<width$}  {}", label, e, width = width),
+                    }
+                }
+            },
+            Commands::EstimateTime => {
+                let config = load_config();
+                let commits = scan_git_repos(config.git_paths.clone(), false, None).unwrap_or_default();
+                if commits.is_empty() {
+                    println!("No commits today.");
+                    return;
+                }
+                let estimate = estimate_active_time(&commits.iter().map(|c| c.time).collect::<Vec<_>>(), config.time_gap_minutes);
+                say!("⏳ {} ({} commits, sessions split at gaps over {} min)", estimate, commits.len(), config.time_gap_minutes);
+            },
+            Commands::ExportBundle { out, mode, source } => {
+                let mut config = load_config();
+                let mut logs = query_today_logs(&conn).unwrap();
+                apply_review_source(&source, &mut logs, &mut config);
+                let commits = scan_git_repos(config.git_paths.clone(), config.deep_analysis, None).unwrap_or_default();
+                // Always a single prompt: chunked reviews would have to call the AI for batch summaries
+                let prompt = generate_prompt_logic(&logs, &commits, &config, &mode);
+                let bundle = serde_json::json!({
+                    "date": Local::now().format("%Y-%m-%d").to_string(),
+                    "generated_at": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                    "mode": mode,
+                    "source": source,
+                    "logs": logs,
+                    "commits": commits,
+                    "prompt": prompt,
+                    "config": masked_config(&config),
+                });
+                match std::fs::write(&out, serde_json::to_string_pretty(&bundle).unwrap()) {
+                    Ok(()) => say!("📦 Wrote {} ({} logs, {} commits, ~{} prompt tokens)", out.display(), logs.len(), commits.len(), estimate_tokens(&prompt)),
+                    Err(e) => say!("❌ Failed to write {}: {}", out.display(), e),
+                }
+            },
+            Commands::Backup { dir } => {
+                let config = load_config();
+                let dir = dir.unwrap_or_else(|| get_backup_dir(&config));
+                match backup_db(&conn, &dir, config.backup_keep) {
+                    Ok(path) => say!("💾 Backed up to {} (keeping the newest {})", path.display(), config.backup_keep.max(1)),
+                    Err(e) => say!("❌ Backup Failed: {}", e),
+                }
+            },
+            Commands::Restore { from, force } => {
+                let confirmed = force || {
+                    print!("{}", styled(&format!("⚠️ Replace all logs and reports with {}? [y/N] ", from.display())));
+                    std::io::Write::flush(&mut std::io::stdout()).unwrap();
+                    let mut answer = String::new();
+                    std::io::stdin().read_line(&mut answer).unwrap();
+                    answer.trim().eq_ignore_ascii_case("y")
+                };
+                if !confirmed {
+                    println!("Cancelled.");
+                    return;
+                }
+                let config = load_config();
+                // In case the wrong backup was picked; nothing is pruned, as that could delete `from`
+                match backup_db(&conn, &get_backup_dir(&config), usize::MAX) {
+                    Ok(path) => say!("💾 Saved the current database to {}", path.display()),
+                    Err(e) => {
+                        say!("❌ Backup of the current database failed, not restoring: {}", e);
+                        return;
+                    }
+                }
+                match restore_db(&mut conn, &from) {
+                    Ok(count) => say!("♻️ Restored {} ({} logs).", from.display(), count),
+                    Err(e) => say!("❌ Restore Failed: {}", e),
+                }
+            },
+            Commands::DbStats => {
+                match db_stats_of(&conn, &get_db_path()) {
+                    Ok(stats) => {
+                        say!("📊 {} logs ({} → {})", stats.total, stats.earliest.as_deref().unwrap_or("-"), stats.latest.as_deref().unwrap_or("-"));
+                        for (log_type, count) in &stats.by_type {
+                            println!("   {:<8} {}", log_type, count);
+                        }
+                        say!("💾 {:.1} KB on disk, {:.1} KB reclaimable by VACUUM", stats.file_size as f64 / 1024.0, stats.reclaimable_bytes as f64 / 1024.0);
+                    },
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Tags => {
+                match tag_counts(&conn) {
+                    Ok(tags) if tags.is_empty() => println!("No #tags in any log yet."),
+                    Ok(tags) => {
+                        let width = tags.iter().map(|(tag, _)| tag.chars().count()).max().unwrap_or(0) + 1;
+                        say!("🏷️ {} tag{}:", tags.len(), if tags.len() == 1 { "" } else { "s" });
+                        for (tag, count) in &tags {
+                            println!("   {:<width$}  {}", format!("#{}", tag), count, width = width);
+                        }
+                    },
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Config { path: true, .. } => {
+                println!("Config:   {}", get_config_path().display());
+                println!("Database: {}", get_db_path().display());
+            },
+            Commands::Config { schedules: true, .. } => {
+                let config = load_config();
+                let state = load_scheduler_state();
+                let last_run = |key: &str| state.get(key).map(|r| format!("  Last: {} {}", r.last_run_date, r.last_run_time)).unwrap_or_default();
+                let day_names = |days: &[u8]| days.iter()
+                    .filter_map(|d| ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].get((*d as usize).wrapping_sub(1)))
+                    .copied().collect::<Vec<_>>().join(",");
+                let path = get_schedules_path();
+                let entries = match load_schedules(&path) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        say!("❌ {}: {}", path.display(), e);
+                        return;
+                    }
+                };
+                if entries.is_empty() {
+                    let Some(time) = config.schedule_time.as_deref() else {
+                        println!("No schedule configured (use --schedule HH:MM or {}).", path.display());
+                        return;
+                    };
+                    let next = match next_fire_time(time, &config.schedule_weekdays, Local::now()) {
+                        Some(next) => next.format("%Y-%m-%d %H:%M %Z").to_string(),
+                        None => "invalid time".to_string(),
+                    };
+                    say!("⏰ {} ({})  Feishu: {}  Next: {}{}", time, day_names(&config.schedule_weekdays), if config.feishu_enabled { "enabled" } else { "disabled" }, next, last_run(DEFAULT_SCHEDULE));
+                } else {
+                    say!("📄 {} (Feishu: {})", path.display(), if config.feishu_enabled { "enabled" } else { "disabled" });
+                    for entry in &entries {
+                        let next = entry.next_fire(&config, Local::now()).map(|t| t.format("%Y-%m-%d %H:%M %:z").to_string()).unwrap_or_default();
+                        let target = entry.apply(&config).feishu_target_email.join(", ");
+                        say!(
+                            "⏰ {}: {} {} ({})  {}  To: {}  Next: {}{}",
+                            entry.name, entry.time, entry.timezone.as_deref().unwrap_or("local"), day_names(&entry.days(&config)),
+                            entry.mode(), if target.is_empty() { "-" } else { &target }, next, last_run(&entry.name)
+                        );
+                    }
+                }
+                if !config.feishu_enabled {
+                    println!("   (the scheduler only runs while Feishu is enabled)");
+                }
+                if let Some(until) = paused_until(&config, Local::now().date_naive()) {
+                    say!("   ⏸️ Paused until {} (da service resume to undo)", until);
+                }
+            },
+            Commands::Config { sinks: true, .. } => {
+                let config = load_config();
+                say!("📮 Delivery sinks:");
+                for check in sink_checks(&config) {
+                    let ok = check.enabled && check.missing.is_empty();
+                    let detail = if check.missing.is_empty() { check.detail } else { format!("missing: {}", check.missing.join(", ")) };
+                    say!("{} {:<8} {:<9} {}", if ok { "✓" } else { "✗" }, check.kind, if check.enabled { "enabled" } else { "disabled" }, detail);
+                }
+                if Sink::enabled(&config).is_empty() {
+                    println!("   (no sink can deliver reports; scheduled reports are generated but not sent)");
+                }
+            },
+            Commands::Config { reset: true, include_secrets, force, .. } => {
+                let confirmed = force || {
+                    let repos = load_config().git_paths.len();
+                    print!("{}", styled(&format!(
+                        "⚠️ Reset all settings to their defaults{}? This also clears your repo list ({} repo{}). [y/N] ",
+                        if include_secrets { ", including API keys and credentials" } else { " (credentials are kept)" },
+                        repos,
+                        if repos == 1 { "" } else { "s" }
+                    )));
+                    std::io::Write::flush(&mut std::io::stdout()).unwrap();
+                    let mut answer = String::new();
+                    std::io::stdin().read_line(&mut answer).unwrap();
+                    answer.trim().eq_ignore_ascii_case("y")
+                };
+                if !confirmed {
+                    println!("Cancelled.");
+                    return;
+                }
+                match save_config_file(&reset_config(&load_config(), include_secrets)) {
+                    Ok(()) => say!("♻️ Config reset to defaults{}.", if include_secrets { "" } else { " (credentials kept)" }),
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Config { reset_field: Some(field), .. } => {
+                match reset_config_field(&load_config(), &field).and_then(|config| save_config_file(&config)) {
+                    Ok(()) => say!("♻️ Reset {} to its default.", field),
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Config { dedupe_repos: true, .. } => {
+                let mut config = load_config();
+                let (merged, missing) = dedupe_repo_paths(&mut config);
+                for path in &missing {
+                    say!("⚠️ Not found: {}", path);
+                }
+                if merged.is_empty() {
+                    say!("✅ No duplicate repos among {} configured.", config.git_paths.len());
+                    return;
+                }
+                for (dup, kept) in &merged {
+                    say!("🔀 {} → {}", dup, kept);
+                }
+                match save_config_file(&config) {
+                    Ok(()) => println!("Removed {} duplicate repo{} ({} left).", merged.len(), if merged.len() == 1 { "" } else { "s" }, config.git_paths.len()),
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Config { lint_template: true, .. } => {
+                let mut templates = vec![("report_template".to_string(), load_config().report_template)];
+                match load_schedules(&get_schedules_path()) {
+                    Ok(entries) => templates.extend(entries.into_iter().filter_map(|e| Some((format!("schedule '{}'", e.name), e.template?)))),
+                    Err(e) => say!("⚠️ Skipping schedules.toml: {}", e),
+                }
+                let mut total = 0;
+                for (name, template) in &templates {
+                    let warnings = lint_template(template);
+                    if warnings.is_empty() {
+                        say!("✅ {} looks good", name);
+                        continue;
+                    }
+                    say!("⚠️ {}:", name);
+                    for w in &warnings {
+                        println!("   {}", w);
+                    }
+                    total += warnings.len();
+                }
+                if total > 0 {
+                    std::process::exit(1);
+                }
+            },
+            Commands::Config { open: true, .. } => {
+                let dir = get_config_path().parent().map(|p| p.to_path_buf()).unwrap_or_default();
+                match open_in_file_manager(&dir) {
+                    Ok(()) => say!("📂 Opened {}", dir.display()),
+                    Err(e) => say!("❌ Failed to open {}: {}", dir.display(), e),
+                }
+            },
+            Commands::Config { show, api_key, add_repo, repo_name, deep_analysis, feishu_app_id, feishu_app_secret, feishu_target, schedule, work_hours_start, work_hours_end, webhook_token, dedupe_threshold, structured_output, include_commit_body, analysis_temperature, export_temperature, chunked_review, chunk_batch_size, add_snippet, toggle_snippet, include_metrics, ai_header, openai_org, openai_project, git_author, detect_git_author, plan_cutoff, min_activity, add_redaction, clear_redactions, add_context_command, clear_context_commands, max_log_length, max_commits_per_repo, commit_order, alert_on_failure, alert_webhook, notify_on_success, all_branches, log_line_format, hook_ai_summary, time_format, rules_file, auto_deep_threshold, ai_concurrency, keep_full_diffs, author_alias, completions_path, ticket_regex, weekdays, provider, model, provider_model, add_profile, analysis_preset, export_preset, time_gap, include_time_estimate, max_prompt_tokens, truncation_strategy, include_wip, git_retries, delivery_footer, report_link, report_prefix, report_suffix, delivery_as_attachment, auto_backup, backup_dir, backup_keep, user_agent, focus_project, .. } => {
                 let mut config = load_config();
                 let mut updated = false;
                 if let Some(k) = api_key { config.api_key = k; updated = true; println!("Updated API Key"); }
                 if let Some(repo) = add_repo { 
                     if !config.git_paths.contains(&repo) { config.git_paths.push(repo); updated = true; println!("Added Repo"); }
                 }
+                if let Some(mapping) = repo_name {
+                    match mapping.rsplit_once('=') {
+                        Some((path, name)) => { config.repo_names.insert(path.to_string(), name.to_string()); updated = true; println!("Set display name for {} to {}", path, name); },
+                        None => say!("❌ Expected PATH=NAME, got: {}", mapping),
+                    }
+                }
                 if let Some(da) = deep_analysis { config.deep_analysis = da; updated = true; println!("Updated Deep Analysis to {}", da); }
                 if let Some(id) = feishu_app_id { config.feishu_app_id = Some(id); updated = true; println!("Updated Feishu App ID"); }
                 if let Some(secret) = feishu_app_secret { config.feishu_app_secret = Some(secret); updated = true; println!("Updated Feishu App Secret"); }
-                if let Some(target) = feishu_target { config.feishu_target_email = Some(target); updated = true; println!("Updated Feishu Target Email"); }
+                if let Some(target) = feishu_target { config.feishu_target_email = split_list(&target); updated = true; println!("Updated Feishu Recipients ({})", config.feishu_target_email.len()); }
                 if let Some(time) = schedule { 
                     config.schedule_time = Some(time); 
                     config.feishu_enabled = true; 
                     updated = true; 
                     println!("Updated Schedule Time & Enabled Feishu"); 
                 }
+                for (value, field, label) in [(work_hours_start, &mut config.work_hours_start, "Start"), (work_hours_end, &mut config.work_hours_end, "End")] {
+                    let Some(value) = value else { continue };
+                    if value.is_empty() {
+                        *field = None; updated = true; println!("Cleared Work Hours {}", label);
+                    } else if NaiveTime::parse_from_str(&value, "%H:%M").is_ok() {
+                        *field = Some(value); updated = true; println!("Updated Work Hours {}", label);
+                    } else {
+                        say!("❌ Invalid work hours {} (expected HH:MM): {}", label.to_lowercase(), value);
+                    }
+                }
+                if let Some(token) = webhook_token { config.webhook_token = Some(token); updated = true; println!("Updated Webhook Token"); }
+                if let Some(t) = dedupe_threshold {
+                    if (0.0..=1.0).contains(&t) {
+                        config.dedupe_threshold = t; updated = true; println!("Updated Dedupe Threshold to {}", t);
+                    } else {
+                        say!("❌ Dedupe threshold must be between 0.0 and 1.0, got: {}", t);
+                    }
+                }
+                if let Some(so) = structured_output { config.structured_output = so; updated = true; println!("Updated Structured Output to {}", so); }
+                if let Some(b) = include_commit_body { config.include_commit_body = b; updated = true; println!("Updated Include Commit Body to {}", b); }
+                if let Some(t) = analysis_temperature { config.analysis_temperature = t; updated = true; println!("Updated Analysis Temperature to {}", t); }
+                if let Some(t) = export_temperature { config.export_temperature = t; updated = true; println!("Updated Export Temperature to {}", t); }
+                if let Some(c) = chunked_review { config.chunked_review = c; updated = true; println!("Updated Chunked Review to {}", c); }
+                if let Some(n) = chunk_batch_size { config.chunk_batch_size = n; updated = true; println!("Updated Chunk Batch Size to {}", n); }
+                if let Some(header) = ai_header {
+                    match header.split_once('=') {
+                        Some((name, value)) => { config.extra_headers.insert(name.to_string(), value.to_string()); updated = true; println!("Set AI Header {}", name); },
+                        None => say!("❌ Expected NAME=VALUE, got: {}", header),
+                    }
+                }
+                if let Some(org) = openai_org { config.openai_org = (!org.is_empty()).then_some(org); updated = true; println!("Updated OpenAI Organization"); }
+                if let Some(project) = openai_project { config.openai_project = (!project.is_empty()).then_some(project); updated = true; println!("Updated OpenAI Project"); }
+                if let Some(author) = git_author {
+                    config.git_author = Some(author).filter(|a| !a.is_empty());
+                    updated = true;
+                    println!("Updated Git Author");
+                }
+                if let Some(d) = detect_git_author { config.detect_git_author = d; updated = true; println!("Updated Detect Git Author to {}", d); }
+                if let Some(cutoff) = plan_cutoff {
+                    if NaiveTime::parse_from_str(&cutoff, "%H:%M").is_ok() {
+                        config.plan_cutoff = cutoff; updated = true; println!("Updated Plan Cutoff");
+                    } else {
+                        say!("❌ Invalid plan cutoff (expected HH:MM): {}", cutoff);
+                    }
+                }
+                if let Some(min) = min_activity { config.min_activity_threshold = min; updated = true; println!("Updated Min Activity Threshold"); }
+                if let Some(m) = include_metrics { config.include_metrics = m; updated = true; println!("Updated Include Metrics to {}", m); }
+                if let Some(snippet) = add_snippet {
+                    match snippet.split_once('=') {
+                        Some((name, text)) => {
+                            config.prompt_snippets.retain(|s| s.name != name);
+                            config.prompt_snippets.push(PromptSnippet { name: name.to_string(), text: text.to_string(), active: false });
+                            updated = true;
+                            println!("Saved Snippet '{}' (use --toggle-snippet to always include it)", name);
+                        },
+                        None => say!("❌ Expected NAME=TEXT, got: {}", snippet),
+                    }
+                }
+                if let Some(max) = max_commits_per_repo { config.max_commits_per_repo = if max == 0 { None } else { Some(max) }; updated = true; println!("Updated Max Commits Per Repo"); }
+                if let Some(order) = commit_order { config.commit_order = order; updated = true; println!("Updated Commit Order"); }
+                if let Some(a) = alert_on_failure { config.alert_on_failure = a; updated = true; println!("Updated Alert On Failure to {}", a); }
+                if let Some(url) = alert_webhook { config.alert_webhook_url = Some(url); updated = true; println!("Updated Alert Webhook"); }
+                if let Some(n) = notify_on_success { config.notify_on_success = n; updated = true; println!("Updated Notify On Success to {}", n); }
+                if let Some(a) = all_branches { config.scan_all_branches = a; updated = true; println!("Updated Scan All Branches to {}", a); }
+                if let Some(f) = log_line_format { config.log_line_format = f; updated = true; println!("Updated Log Line Format"); }
+                if let Some(h) = hook_ai_summary { config.hook_ai_summary = h; updated = true; println!("Updated Hook AI Summary to {}", h); }
+                if let Some(f) = time_format {
+                    if chrono::format::StrftimeItems::new(&f).any(|i| matches!(i, chrono::format::Item::Error)) {
+                        say!("❌ Invalid time format: {}", f);
+                    } else {
+                        config.time_display_format = f; updated = true; println!("Updated Time Display Format");
+                    }
+                }
+                if let Some(path) = rules_file {
+                    config.custom_rules_path = if path.is_empty() { None } else { Some(path) };
+                    updated = true;
+                    println!("Updated Rules File");
+                }
+                if let Some(t) = auto_deep_threshold { config.auto_deep_threshold = if t == 0 { None } else { Some(t) }; updated = true; println!("Updated Auto Deep Threshold"); }
+                if let Some(n) = ai_concurrency { config.ai_concurrency = n.max(1); updated = true; println!("Updated AI Concurrency to {}", n.max(1)); }
+                if let Some(k) = keep_full_diffs { config.keep_full_diffs = k; updated = true; println!("Updated Keep Full Diffs to {}", k); }
+                if let Some(alias) = author_alias {
+                    match alias.split_once('=') {
+                        Some((alias, name)) => { config.author_aliases.insert(alias.to_string(), name.to_string()); updated = true; println!("Mapped Author '{}' to '{}'", alias, name); },
+                        None => say!("❌ Expected ALIAS=NAME, got: {}", alias),
+                    }
+                }
+                if let Some(path) = completions_path {
+                    if path.is_empty() {
+                        config.completions_path = None; updated = true; println!("Reset Completions Path");
+                    } else if let Err(e) = check_completions_path(&path) {
+                        say!("❌ {}", e);
+                    } else {
+                        config.completions_path = Some(path); updated = true; println!("Updated Completions Path");
+                    }
+                }
+                if let Some(pattern) = ticket_regex {
+                    if pattern.is_empty() {
+                        config.ticket_regex = None; updated = true; println!("Disabled Ticket Grouping");
+                    } else {
+                        match regex::Regex::new(&pattern) {
+                            Ok(_) => { config.ticket_regex = Some(pattern); updated = true; println!("Updated Ticket Regex"); },
+                            Err(e) => say!("❌ Invalid regex: {}", e),
+                        }
+                    }
+                }
+                if let Some(list) = weekdays {
+                    match parse_weekdays(&list) {
+                        Ok(days) if !days.is_empty() => { config.schedule_weekdays = days; updated = true; println!("Updated Schedule Weekdays"); },
+                        Ok(_) => say!("❌ Give at least one weekday."),
+                        Err(e) => say!("❌ {}", e),
+                    }
+                }
+                if let Some(mapping) = provider_model {
+                    match mapping.split_once('=') {
+                        Some((p, m)) if !m.is_empty() => { config.provider_models.insert(p.to_string(), m.to_string()); updated = true; println!("Set Default Model for {} to {}", p, m); },
+                        _ => say!("❌ Expected PROVIDER=MODEL, got: {}", mapping),
+                    }
+                }
+                if let Some(provider) = provider {
+                    if let Some((base_url, _)) = provider_preset(&provider) {
+                        let default_model = config.default_model(&provider).unwrap_or_default();
+                        config.base_url = Some(base_url.to_string());
+                        config.model = default_model.clone();
+                        println!("Using {} defaults: {} ({})", provider, base_url, default_model);
+                    }
+                    config.provider = provider; updated = true; println!("Updated Provider");
+                }
+                if let Some(m) = model { config.model = m; updated = true; println!("Updated Model"); }
+                if let Some(profile) = add_profile {
+                    match profile.split_once('=').ok_or("expected NAME=PROVIDER,KEY[,MODEL]".to_string()).and_then(|(name, spec)| AiProfile::parse(name, spec)) {
+                        Ok(p) => {
+                            println!("Saved Profile '{}' ({}, {})", p.name, p.provider, p.model);
+                            config.ai_profiles.retain(|existing| existing.name != p.name);
+                            config.ai_profiles.push(p);
+                            updated = true;
+                        },
+                        Err(e) => say!("❌ {}", e),
+                    }
+                }
+                for (label, preset, slot) in [("Analysis", analysis_preset, &mut config.analysis_preset), ("Export", export_preset, &mut config.export_preset)] {
+                    match preset {
+                        Some(name) if name.is_empty() => { *slot = None; updated = true; println!("{} now uses the main model", label); }
+                        Some(name) if config.ai_profiles.iter().any(|p| p.name == name) => { println!("Updated {} Preset to {}", label, name); *slot = Some(name); updated = true; }
+                        Some(name) => say!("❌ Unknown model preset '{}' (add it with --add-profile)", name),
+                        None => {}
+                    }
+                }
+                if let Some(gap) = time_gap { config.time_gap_minutes = gap.max(1); updated = true; println!("Updated Time Gap to {} min", gap.max(1)); }
+                if let Some(t) = include_time_estimate { config.include_time_estimate = t; updated = true; println!("Updated Include Time Estimate to {}", t); }
+                if let Some(max) = max_prompt_tokens { config.max_prompt_tokens = (max > 0).then_some(max); updated = true; println!("Updated Max Prompt Tokens"); }
+                if let Some(strategy) = truncation_strategy { config.truncation_strategy = strategy; updated = true; println!("Updated Truncation Strategy"); }
+                if let Some(n) = git_retries { config.git_retries = n; updated = true; println!("Updated Git Retries to {}", n); }
+                if let Some(w) = include_wip { config.include_wip = w; updated = true; println!("Updated Include WIP to {}", w); }
+                if let Some(footer) = delivery_footer { config.delivery_footer = footer.replace("\\n", "\n"); updated = true; println!("Updated Delivery Footer"); }
+                if let Some(link) = report_link { config.report_link = (!link.is_empty()).then_some(link); updated = true; println!("Updated Report Link"); }
+                if let Some(prefix) = report_prefix { config.report_prefix = prefix.replace("\\n", "\n"); updated = true; println!("Updated Report Prefix"); }
+                if let Some(suffix) = report_suffix { config.report_suffix = suffix.replace("\\n", "\n"); updated = true; println!("Updated Report Suffix"); }
+                if let Some(a) = delivery_as_attachment { config.delivery_as_attachment = a; updated = true; println!("Updated Delivery As Attachment to {}", a); }
+                if let Some(b) = auto_backup { config.auto_backup = b; updated = true; println!("Updated Auto Backup to {}", b); }
+                if let Some(dir) = backup_dir { config.backup_dir = (!dir.is_empty()).then_some(dir); updated = true; println!("Updated Backup Directory"); }
+                if let Some(n) = backup_keep { config.backup_keep = n.max(1); updated = true; println!("Updated Backup Keep to {}", n.max(1)); }
+                if let Some(focus) = focus_project { config.focus_project = (!focus.is_empty()).then_some(focus); updated = true; println!("Updated Focus Project"); }
+                if let Some(ua) = user_agent { config.user_agent = (!ua.is_empty()).then_some(ua); updated = true; println!("Updated User-Agent"); }
+                if clear_redactions { config.redaction_patterns.clear(); updated = true; println!("Cleared Redaction Patterns"); }
+                if let Some(pattern) = add_redaction {
+                    match regex::Regex::new(&pattern) {
+                        Ok(_) => { config.redaction_patterns.push(pattern); updated = true; println!("Added Redaction Pattern"); },
+                        Err(e) => say!("❌ Invalid regex: {}", e),
+                    }
+                }
+                if let Some(max) = max_log_length { config.max_log_length = (max > 0).then_some(max); updated = true; println!("Updated Max Log Length"); }
+                if clear_context_commands { config.context_commands.clear(); updated = true; println!("Cleared Context Commands"); }
+                if let Some(cmd) = add_context_command { config.context_commands.push(cmd); updated = true; println!("Added Context Command"); }
+                if let Some(name) = toggle_snippet {
+                    match config.prompt_snippets.iter_mut().find(|s| s.name == name) {
+                        Some(s) => { s.active = !s.active; updated = true; println!("Snippet '{}' is now {}", name, if s.active { "active" } else { "inactive" }); },
+                        None => say!("❌ Snippet '{}' not found.", name),
+                    }
+                }
                 
                 if updated { save_config_file(&config).unwrap(); }
-                println!("Current Config: {:#?}", config);
+                if show {
+                    println!("{}", serde_json::to_string_pretty(&masked_config(&config)).unwrap());
+                } else {
+                    println!("Current Config: {:#?}", config);
+                }
             },
-            Commands::Sync { deep } => {
-                let config = load_config();
+            Commands::Sync { deep, repos, exclude_repos, until } => {
+                let mut config = load_config();
+                config.git_paths = filter_repo_paths(&config, &repos, &exclude_repos);
                 let use_deep = deep || config.deep_analysis;
-                println!("🔄 Syncing Git Repos (Deep Analysis: {})...", use_deep);
+                say!("🔄 Syncing Git Repos (Deep Analysis: {})...", use_deep);
                 
-                match scan_git_repos(config.git_paths.clone(), use_deep) {
+                match scan_git_repos(config.git_paths.clone(), use_deep, until) {
                     Ok(commits) => {
                          for c in commits {
-                             println!("[{}] {} ({})", c.repo_name.unwrap_or_default(), c.message, c.author);
+                             let time = DateTime::from_timestamp(c.time, 0).map(|t| t.with_timezone(&Local).format("%H:%M").to_string()).unwrap_or_else(|| "--:--".to_string());
+                             println!("{} [{}] {} ({})", time, c.repo_name.unwrap_or_default(), c.message, c.author);
                              if let Some(diff) = c.diff { println!("   Diff: {} bytes", diff.len()); }
                          }
                     },
-                    Err(e) => println!("❌ Sync Failed: {}", e),
+                    Err(e) => say!("❌ Sync Failed: {}", e),
+                }
+            },
+            Commands::Commits { since, repos, exclude_repos } => {
+                let config = load_config();
+                let paths = filter_repo_paths(&config, &repos, &exclude_repos);
+                let mut commits = match scan_git_window(paths, false, &format!("--since={}", since), None) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        say!("❌ {}", e);
+                        return;
+                    }
+                };
+                if commits.is_empty() {
+                    println!("No commits since {}.", since);
+                    return;
+                }
+                commits.sort_by_key(|c| c.time);
+                let mut repos: Vec<(String, Vec<&GitCommit>)> = vec![];
+                for c in &commits {
+                    let name = c.repo_name.clone().unwrap_or_default();
+                    match repos.iter_mut().find(|(r, _)| *r == name) {
+                        Some((_, group)) => group.push(c),
+                        None => repos.push((name, vec![c])),
+                    }
+                }
+                let today = Local::now().date_naive();
+                for (repo, group) in &repos {
+                    let omitted: usize = group.iter().map(|c| c.omitted).sum();
+                    say!("\n📂 {} ({})", repo, group.len() + omitted);
+                    for c in group {
+                        let time = DateTime::from_timestamp(c.time, 0).map(|t| t.with_timezone(&Local));
+                        let time = match time {
+                            Some(t) if t.date_naive() == today => t.format("%H:%M").to_string(),
+                            Some(t) => t.format("%m-%d %H:%M").to_string(),
+                            None => "--:--".to_string(),
+                        };
+                        let short_hash: String = c.hash.chars().take(7).collect();
+                        println!("  {}  {}  {}", time, short_hash, c.message);
+                    }
+                    if omitted > 0 {
+                        println!("  (and {} more)", omitted);
+                    }
                 }
             },
-            Commands::Review { export } => {
-                let config = load_config();
+            Commands::Review { export, plan, summary, out, json, snippets, repos, exclude_repos, model_preset, focus, date, source, until } => {
+                if json {
+                    QUIET.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+                if date.is_some_and(|d| d > Local::now().date_naive()) {
+                    say!("❌ {} is in the future.", date.unwrap());
+                    return;
+                }
+                // The scan itself can't fail the review, so catch a bad bound before calling the AI
+                if let Some(Err(e)) = until.as_deref().map(|u| parse_until(u, date.unwrap_or_else(|| Local::now().date_naive()))) {
+                    say!("❌ {}", e);
+                    return;
+                }
+                let mut config = load_config();
+                if focus.is_some() { config.focus_project = focus; }
+                if let Some(name) = model_preset {
+                    config = match config.with_profile(&name) {
+                        // An explicit preset wins over the per-mode defaults
+                        Ok(c) => AppConfig { analysis_preset: None, export_preset: None, ..c },
+                        Err(e) => {
+                            say!("❌ {}", e);
+                            return;
+                        }
+                    };
+                }
+                config.git_paths = filter_repo_paths(&config, &repos, &exclude_repos);
+                for name in &snippets {
+                    match config.prompt_snippets.iter_mut().find(|s| &s.name == name) {
+                        Some(snippet) => snippet.active = true,
+                        None => say_err!("⚠️ Unknown snippet: {}", name),
+                    }
+                }
                 let rt = tokio::runtime::Runtime::new().unwrap();
                 
                 // 1. Logs
-                let mut logs = vec![];
-                let mut stmt = conn.prepare("SELECT id, content, log_type, timestamp FROM logs WHERE date(timestamp) = date('now', 'localtime') ORDER BY id DESC").unwrap();
-                let iter = stmt.query_map([], |row| Ok(LogItem { id: row.get(0)?, content: row.get(1)?, log_type: row.get(2)?, timestamp: row.get(3)? })).unwrap();
-                for l in iter { logs.push(l.unwrap()); }
-
-                // 2. Commits
-                let use_deep = config.deep_analysis;
-                let commits = scan_git_repos(config.git_paths.clone(), use_deep).unwrap_or_default();
-
-                // 3. Generate Prompt
-                let mode = if export { "export" } else { "analysis" };
-                println!("🤔 Generating AI {}...", if export { "Report" } else { "Review" });
-                let prompt = generate_prompt_logic(&logs, &commits, &config, mode);
-
-                // 4. Call AI
-                let req = AiRequest {
-                    provider: config.provider.clone(),
-                    api_key: config.api_key.clone(),
-                    model: config.model.clone(),
-                    base_url: config.base_url.clone(),
-                    prompt,
+                let mut logs = match date {
+                    Some(d) => query_logs_on(&conn, d).unwrap(),
+                    None => query_today_logs(&conn).unwrap(),
                 };
+                apply_review_source(&source, &mut logs, &mut config);
 
-                match rt.block_on(call_ai(req)) {
-                    Ok(res) => println!("\n{}", res),
-                    Err(e) => println!("❌ AI Error: {}", e),
+                // 2. Commits, Prompt & AI
+                let mode = if export { "export" } else if plan { "plan" } else if summary { "summary" } else { "analysis" };
+                if !json { say!("🤔 Generating AI {}...", if export { "Report" } else if plan { "Plan Review" } else if summary { "Summary" } else { "Review" }); }
+
+                if let (Some(d), false) = (date, json) { say!("📅 Replaying {} ({} logs)", d, logs.len()); }
+                match rt.block_on(generate_review(&logs, &config, mode, date, until.as_deref())) {
+                    Ok(res) => {
+                        let output = if json {
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "mode": mode,
+                                "date": date.unwrap_or_else(|| Local::now().date_naive()).format("%Y-%m-%d").to_string(),
+                                "generated_at": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                                "report": res,
+                            })).unwrap()
+                        } else {
+                            res
+                        };
+                        if json { println!("{}", output); } else { println!("\n{}", output); }
+                        if let Some(path) = out {
+                            match write_report_file(&path, &output, json) {
+                                Ok(()) => if !json { say!("📝 Saved to {}", path.display()); },
+                                Err(e) => say!("❌ Failed to write {}: {}", path.display(), e),
+                            }
+                        }
+                    },
+                    Err(e) => say!("❌ AI Error: {}", e),
                 }
             }
 
 
+            Commands::Monthly { month, out } => {
+                let month = month.unwrap_or_else(|| Local::now().format("%Y-%m").to_string());
+                let Ok(start) = chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d") else {
+                    say!("❌ Invalid month '{}' (expected YYYY-MM).", month);
+                    return;
+                };
+                let end = start.checked_add_months(chrono::Months::new(1)).map(|d| d - chrono::Days::new(1)).unwrap_or(start);
+                if start > Local::now().date_naive() {
+                    say!("❌ {} is in the future.", month);
+                    return;
+                }
+                let config = load_config();
+                let logs = query_logs_between(&conn, start, end).unwrap();
+                // Messages only: a month of diffs wouldn't fit any prompt
+                let commits = scan_git_window(config.git_paths.clone(), false, &format!("--since={} 00:00:00", start), Some(format!("--until={} 23:59:59", end).as_str())).unwrap_or_default();
+                say!("🗓️ Generating Monthly Report for {} ({} logs, {} commits)...", month, logs.len(), commits.len());
+                let rt = Runtime::new().unwrap();
+                match rt.block_on(generate_monthly_report(&logs, &commits, &config, start, end)) {
+                    Ok(res) => {
+                        println!("\n{}", res);
+                        if let Some(path) = out {
+                            match write_report_file(&path, &res, false) {
+                                Ok(()) => say!("📝 Saved to {}", path.display()),
+                                Err(e) => say!("❌ Failed to write {}: {}", path.display(), e),
+                            }
+                        }
+                    },
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
+            Commands::Standup => {
+                let config = load_config();
+                let yesterday = query_yesterday_logs(&conn).unwrap();
+                let today = query_today_logs(&conn).unwrap();
+                let blockers = query_open_blockers(&conn).unwrap();
+
+                say!("🧍 Generating Standup...");
+                let mut req = build_ai_request(&config, generate_standup_prompt(&yesterday, &today, &blockers, &config), "analysis");
+                req.response_format = None;
+                let rt = Runtime::new().unwrap();
+                match rt.block_on(call_ai(req)) {
+                    Ok(res) => println!("\n{}", res),
+                    Err(e) => say!("❌ AI Error: {}", e),
+                }
+            },
+            Commands::HookSummary { from, repo } => {
+                let config = load_config();
+                // The default "." has no directory name; resolve it, preferring the configured
+                // spelling of the same repo so its display name applies
+                let canonical = std::fs::canonicalize(&repo).map(|p| p.to_string_lossy().into_owned()).unwrap_or(repo);
+                let repo = config.git_paths.iter()
+                    .find(|p| std::fs::canonicalize(p).is_ok_and(|c| c.to_string_lossy() == canonical))
+                    .cloned()
+                    .unwrap_or(canonical);
+                // Without a previous HEAD, just take the latest commit (which also works for a repo's first commit)
+                let range = match from.or_else(|| std::env::var("DA_PREV_HEAD").ok()) {
+                    Some(from) => format!("{}..HEAD", from),
+                    None => "-1".to_string(),
+                };
+                let commits = match commits_in_range(&repo, &range) {
+                    Ok(c) if c.is_empty() => return,
+                    Ok(c) => c,
+                    Err(e) => {
+                        say!("❌ {}", e);
+                        return;
+                    }
+                };
+                let subjects = commits.iter().rev().map(|c| c.message.as_str()).collect::<Vec<_>>().join("; ");
+                // An offline stub reply would be saved as a real note, so keep the raw subjects
+                let summary = if config.hook_ai_summary && !offline() {
+                    let prompt = format!(
+                        "Summarize these git commits as a single short line for a work journal (no bullet, no quotes):\n{}",
+                        format_commits_for_prompt(&redact_inputs(&[], &commits, &config).1)
+                    );
+                    let mut req = build_ai_request(&config, prompt, "analysis");
+                    req.response_format = None;
+                    let rt = Runtime::new().unwrap();
+                    match rt.block_on(call_ai(req)) {
+                        Ok(res) => res.lines().next().unwrap_or_default().trim().to_string(),
+                        Err(e) => {
+                            // Never block the commit workflow on the AI; fall back to the raw subjects
+                            say_err!("⚠️ AI Error: {}", e);
+                            subjects
+                        }
+                    }
+                } else {
+                    subjects
+                };
+                let content = format!("[{}] {}", repo_display_name(&config, &repo), summary);
+                match insert_log(&conn, &content, "note") {
+                    Ok(()) => say!("📝 {}", content),
+                    Err(e) => say!("❌ Failed to save: {}", e),
+                }
+            },
+            Commands::Send { text, file } => {
+                let text = match (text, file) {
+                    (Some(text), _) => text,
+                    (None, Some(path)) => match std::fs::read_to_string(&path) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            say!("❌ Failed to read {}: {}", path.display(), e);
+                            return;
+                        }
+                    },
+                    (None, None) => {
+                        say!("❌ Provide --text or --file.");
+                        return;
+                    }
+                };
+                let rt = Runtime::new().unwrap();
+                match rt.block_on(send_text(text)) {
+                    Ok(results) => say!("📬 Delivery: {}", format_sink_results(&results)),
+                    Err(e) => say!("❌ {}", e),
+                }
+            },
             Commands::Service { action } => {
                 match action {
                      ServiceCommands::Status => {
@@ -536,9 +4109,9 @@ pub fn run() {
                          let stdout2 = String::from_utf8_lossy(&output2.stdout);
 
                          if stdout1.contains("daily-assistant.exe") || stdout2.contains("da.exe") {
-                             println!("🟢 Service is RUNNING.");
+                             say!("🟢 Service is RUNNING.");
                          } else {
-                             println!("🔴 Service is STOPPED.");
+                             say!("🔴 Service is STOPPED.");
                          }
                      },
                      ServiceCommands::Start => {
@@ -561,7 +4134,7 @@ pub fn run() {
                                  .expect("Failed to start daemon service");
                          }
                          
-                         println!("🚀 Service Started (Background Mode).");
+                         say!("🚀 Service Started (Background Mode).");
                      },
                      ServiceCommands::Stop => {
                          let _ = Command::new("taskkill")
@@ -571,7 +4144,44 @@ pub fn run() {
                          let _ = Command::new("taskkill")
                              .args(&["/F", "/IM", "da.exe"])
                              .output();
-                         println!("🛑 Service Stopped.");
+                         say!("🛑 Service Stopped.");
+                     },
+                     ServiceCommands::TestAi => {
+                         let config = load_config();
+                         say!("🔌 Testing AI connection ({})...", config.model);
+                         let rt = Runtime::new().unwrap();
+                         match rt.block_on(test_ai_connection(&config)) {
+                             Ok(r) => say!("✅ AI OK: model {} replied \"{}\" in {} ms", r.model, r.reply, r.latency_ms),
+                             Err(e) => say!("❌ AI Test Failed: {}", e),
+                         }
+                     }
+                     ServiceCommands::TestRun => {
+                         say!("🧪 Test run: sample logs and one fake commit, nothing is saved...");
+                         let rt = Runtime::new().unwrap();
+                         match rt.block_on(run_test_pipeline(load_config())) {
+                             Ok((report, results)) => {
+                                 // Only the terminal copy follows --plain; sinks got the banner as-is
+                                 println!("{}", report.replacen(TEST_RUN_BANNER, &styled(TEST_RUN_BANNER), 1));
+                                 if results.is_empty() {
+                                     say!("⚠️ No delivery sinks configured, the report was not sent.");
+                                 } else {
+                                     say!("📬 Delivery: {}", format_sink_results(&results));
+                                 }
+                             }
+                             Err(e) => say!("❌ Test Run Failed: {}", e),
+                         }
+                     }
+                     ServiceCommands::Pause { until } => {
+                         let mut config = load_config();
+                         config.paused_until = Some(until.format("%Y-%m-%d").to_string());
+                         save_config_file(&config).unwrap();
+                         say!("⏸️ Scheduled reports paused until {}.", until);
+                     }
+                     ServiceCommands::Resume => {
+                         let mut config = load_config();
+                         config.paused_until = None;
+                         save_config_file(&config).unwrap();
+                         say!("▶️ Scheduled reports resumed.");
                      }
                 }
             },
@@ -585,13 +4195,14 @@ pub fn run() {
 
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
-            save_log, delete_log, get_today_logs, scan_git_repos, call_ai,
+            save_log, delete_log, get_today_logs, complete_log, purge_logs, merge_db, db_stats, get_tags, get_full_diff, search_logs_regex, render_report_html, scan_git_repos, call_ai, generate_structured_report, test_ai, send_text,
             get_config, save_config
         ])
         .setup(|app| {
             let db_path = get_db_path();
             let db_state = DbState::init(db_path).expect("Failed to initialize database");
             app.manage(db_state);
+            let _ = PROGRESS_APP.set(app.handle().clone());
             
             // Start Scheduler
             // Start Scheduler (Thread) - Only if running GUI mode
@@ -609,43 +4220,42 @@ pub fn run() {
 struct FeishuClient {
     app_id: String,
     app_secret: String,
+    client: Client,
 }
 
 impl FeishuClient {
-    fn new(app_id: String, app_secret: String) -> Self {
-        Self { app_id, app_secret }
+    fn new(app_id: String, app_secret: String, client: Client) -> Self {
+        Self { app_id, app_secret, client }
     }
 
-    async fn get_token(&self) -> Result<String, String> {
-        let client = Client::new();
-        let res = client.post("https://open.feishu.cn/open-apis/auth/v3/tenant_access_token/internal")
+    async fn get_token(&self) -> AppResult<String> {
+        let res = self.client.post("https://open.feishu.cn/open-apis/auth/v3/tenant_access_token/internal")
             .json(&serde_json::json!({
                 "app_id": self.app_id,
                 "app_secret": self.app_secret
             }))
             .send()
-            .await.map_err(|e| e.to_string())?;
+            .await?;
             
-        let json: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+        let json: serde_json::Value = res.json().await?;
         if let Some(token) = json.get("tenant_access_token") {
             Ok(token.as_str().unwrap().to_string())
         } else {
-            Err(format!("Auth Failed: {:?}", json))
+            Err(AppError::Http(format!("Auth Failed: {:?}", json)))
         }
     }
 
-    async fn get_user_id(&self, token: &str, email: &str) -> Result<String, String> {
-        let client = Client::new();
+    async fn get_user_id(&self, token: &str, email: &str) -> AppResult<String> {
         let url = "https://open.feishu.cn/open-apis/contact/v3/users/batch_get_id?user_id_type=open_id";
-        let res = client.post(url)
+        let res = self.client.post(url)
             .header("Authorization", format!("Bearer {}", token))
             .json(&serde_json::json!({
                 "emails": [email]
             }))
             .send()
-            .await.map_err(|e| e.to_string())?;
+            .await?;
             
-        let json: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+        let json: serde_json::Value = res.json().await?;
         // Path: data.user_list[0].user_id
         if let Some(list) = json.get("data").and_then(|d| d.get("user_list")).and_then(|l| l.as_array()) {
             if let Some(user) = list.first() {
@@ -654,13 +4264,12 @@ impl FeishuClient {
                 }
             }
         }
-        Err(format!("User not found for email: {}", email))
+        Err(AppError::NotFound(format!("User not found for email: {}", email)))
     }
 
-    async fn send_message(&self, token: &str, receive_id: &str, content: &str) -> Result<(), String> {
-        let client = Client::new();
+    async fn send_message(&self, token: &str, receive_id: &str, content: &str) -> AppResult<()> {
         let url = "https://open.feishu.cn/open-apis/im/v1/messages?receive_id_type=open_id";
-        let res = client.post(url)
+        let res = self.client.post(url)
             .header("Authorization", format!("Bearer {}", token))
             .json(&serde_json::json!({
                 "receive_id": receive_id,
@@ -668,43 +4277,514 @@ impl FeishuClient {
                 "content": serde_json::json!({ "text": content }).to_string()
             }))
             .send()
-            .await.map_err(|e| e.to_string())?;
+            .await?;
             
         let status = res.status();
         if !status.is_success() {
              let text = res.text().await.unwrap_or_default();
-             return Err(format!("Send failed: {} - {}", status, text));
+             return Err(AppError::Http(format!("Send failed: {} - {}", status, text)));
+        }
+        Ok(())
+    }
+
+    // Uploads `bytes` as a generic file and returns the file_key used to send it.
+    async fn upload_file(&self, token: &str, file_name: &str, bytes: Vec<u8>) -> AppResult<String> {
+        let url = "https://open.feishu.cn/open-apis/im/v1/files";
+        let part = reqwest::multipart::Part::bytes(bytes).file_name(file_name.to_string());
+        let form = reqwest::multipart::Form::new()
+            .text("file_type", "stream")
+            .text("file_name", file_name.to_string())
+            .part("file", part);
+        let res = self.client.post(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .multipart(form)
+            .send()
+            .await?;
+
+        let json: serde_json::Value = res.json().await?;
+        // Path: data.file_key
+        match json.get("data").and_then(|d| d.get("file_key")).and_then(|k| k.as_str()) {
+            Some(key) => Ok(key.to_string()),
+            None => Err(AppError::Http(format!("File upload failed: {:?}", json))),
+        }
+    }
+
+    async fn send_file(&self, token: &str, receive_id: &str, file_key: &str) -> AppResult<()> {
+        let url = "https://open.feishu.cn/open-apis/im/v1/messages?receive_id_type=open_id";
+        let res = self.client.post(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({
+                "receive_id": receive_id,
+                "msg_type": "file",
+                "content": serde_json::json!({ "file_key": file_key }).to_string()
+            }))
+            .send()
+            .await?;
+
+        let status = res.status();
+        if !status.is_success() {
+             let text = res.text().await.unwrap_or_default();
+             return Err(AppError::Http(format!("Send failed: {} - {}", status, text)));
         }
         Ok(())
     }
 }
 
 // Scheduler Logic
+// Accepts "9:05" as well as "09:05".
+fn parse_schedule_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+// The next moment at or after `now` (to the minute) when a daily schedule fires, on one of
+// `weekdays` (1 = Monday .. 7 = Sunday), in the timezone of `now`.
+fn next_fire_time<Tz: chrono::TimeZone>(time: &str, weekdays: &[u8], now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let time = parse_schedule_time(time)?;
+    let minute = now.with_second(0)?.with_nanosecond(0)?;
+    (0..8).find_map(|offset| {
+        let day = now.date_naive() + chrono::Days::new(offset);
+        if !weekdays.contains(&(day.weekday().number_from_monday() as u8)) { return None; }
+        day.and_time(time).and_local_timezone(now.timezone()).earliest().filter(|t| *t >= minute)
+    })
+}
+
+const DEFAULT_SCHEDULE: &str = "default";
+
+// When a schedule last fired, in the schedule's own timezone. Kept per schedule name
+// (DEFAULT_SCHEDULE for `schedule_time`) in scheduler_state.json so it survives restarts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub last_run_date: String,
+    pub last_run_time: String,
+}
+
+fn get_scheduler_state_path() -> std::path::PathBuf {
+    get_config_path().with_file_name("scheduler_state.json")
+}
+
+fn load_scheduler_state() -> std::collections::HashMap<String, ScheduleRun> {
+    std::fs::read_to_string(get_scheduler_state_path()).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn save_scheduler_state(state: &std::collections::HashMap<String, ScheduleRun>) -> AppResult<()> {
+    let content = serde_json::to_string_pretty(state).map_err(|e| AppError::Config(e.to_string()))?;
+    std::fs::write(get_scheduler_state_path(), content).map_err(|e| AppError::Config(e.to_string()))
+}
+
+// One entry of `schedules.toml` (next to config.json). When the file has entries they
+// replace `schedule_time`:
+//
+//   [[schedule]]
+//   name = "team"
+//   time = "18:00"
+//   timezone = "Asia/Shanghai"       # optional, defaults to local time
+//   weekdays = "mon,tue,wed,thu,fri" # optional, defaults to `schedule_weekdays`
+//   mode = "export"                  # optional, "analysis" (default), "export" or "summary" (one-line TL;DR)
+//   template = "..."                 # optional, replaces `report_template`
+//   sinks = ["feishu:lead@x.com"]    # optional, "feishu" (all recipients) or "feishu:EMAIL"
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleEntry {
+    pub name: String,
+    pub time: String,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub weekdays: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub sinks: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    schedule: Vec<ScheduleEntry>,
+}
+
+impl ScheduleEntry {
+    fn tz(&self) -> Result<Option<chrono_tz::Tz>, String> {
+        self.timezone.as_deref().map(|tz| tz.parse().map_err(|_| format!("unknown timezone '{}'", tz))).transpose()
+    }
+
+    fn days(&self, config: &AppConfig) -> Vec<u8> {
+        self.weekdays.as_deref().and_then(|d| parse_weekdays(d).ok()).unwrap_or_else(|| config.schedule_weekdays.clone())
+    }
+
+    fn mode(&self) -> &str {
+        self.mode.as_deref().unwrap_or("analysis")
+    }
+
+    fn next_fire(&self, config: &AppConfig, now: DateTime<Local>) -> Option<DateTime<chrono::FixedOffset>> {
+        match self.tz().ok().flatten() {
+            Some(tz) => next_fire_time(&self.time, &self.days(config), now.with_timezone(&tz)).map(|t| t.fixed_offset()),
+            None => next_fire_time(&self.time, &self.days(config), now).map(|t| t.fixed_offset()),
+        }
+    }
+
+    // The config this entry's report is generated and delivered with.
+    fn apply(&self, config: &AppConfig) -> AppConfig {
+        let mut config = config.clone();
+        if let Some(template) = &self.template {
+            config.report_template = template.clone();
+        }
+        if let Some(sinks) = &self.sinks {
+            let mut emails = if sinks.iter().any(|s| s == "feishu") { config.feishu_target_email.clone() } else { vec![] };
+            for email in sinks.iter().filter_map(|s| s.strip_prefix("feishu:")) {
+                if !emails.iter().any(|e| e == email) { emails.push(email.to_string()); }
+            }
+            config.feishu_target_email = emails;
+        }
+        config
+    }
+}
+
+fn get_schedules_path() -> std::path::PathBuf {
+    get_config_path().with_file_name("schedules.toml")
+}
+
+// Parses and validates `schedules.toml`; a missing file has no entries.
+fn load_schedules(path: &std::path::Path) -> Result<Vec<ScheduleEntry>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+    let file: ScheduleFile = toml::from_str(&content).map_err(|e| e.to_string())?;
+    for (i, entry) in file.schedule.iter().enumerate() {
+        let err = |msg: String| format!("schedule '{}': {}", entry.name, msg);
+        if file.schedule[..i].iter().any(|e| e.name == entry.name) {
+            return Err(err("duplicate name".to_string()));
+        }
+        parse_schedule_time(&entry.time).ok_or_else(|| err(format!("invalid time '{}'", entry.time)))?;
+        entry.tz().map_err(err)?;
+        if let Some(days) = &entry.weekdays {
+            parse_weekdays(days).map_err(err)?;
+        }
+        if !["analysis", "export", "summary"].contains(&entry.mode()) {
+            return Err(err(format!("unknown mode '{}'", entry.mode())));
+        }
+        if let Some(sink) = entry.sinks.iter().flatten().find(|s| *s != "feishu" && !s.starts_with("feishu:")) {
+            return Err(err(format!("unknown sink '{}'", sink)));
+        }
+    }
+    Ok(file.schedule)
+}
+
+// Parses "mon,tue,fri" or "1,2,5" into weekday numbers (1 = Monday .. 7 = Sunday).
+fn parse_weekdays(list: &str) -> Result<Vec<u8>, String> {
+    const NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+    let mut days = vec![];
+    for item in list.split(',').map(|d| d.trim().to_lowercase()).filter(|d| !d.is_empty()) {
+        let day = match item.parse::<u8>() {
+            Ok(n @ 1..=7) => n,
+            _ => NAMES.iter().position(|n| item.starts_with(n)).map(|i| i as u8 + 1).ok_or(format!("unknown weekday '{}'", item))?,
+        };
+        if !days.contains(&day) { days.push(day); }
+    }
+    days.sort();
+    Ok(days)
+}
+
+// The pause date while it's still in the future; reports resume on that date.
+// The day a run at `now` reports on (in the schedule's timezone, else local time), with that
+// day's start and end converted to local time to match log timestamps and git's --since/--until.
+fn report_window(schedule: Option<&ScheduleEntry>, now: DateTime<Local>) -> (chrono::NaiveDate, chrono::NaiveDateTime, chrono::NaiveDateTime) {
+    let tz = schedule.and_then(|e| e.tz().ok().flatten());
+    let day = match tz {
+        Some(tz) => now.with_timezone(&tz).date_naive(),
+        None => now.date_naive(),
+    };
+    let midnight = |d: chrono::NaiveDate| {
+        let local = d.and_time(NaiveTime::MIN);
+        match tz {
+            // A DST jump over midnight makes the local time ambiguous or missing; take the first valid instant
+            Some(tz) => chrono::TimeZone::from_local_datetime(&tz, &local).earliest()
+                .unwrap_or_else(|| chrono::TimeZone::from_utc_datetime(&tz, &local))
+                .with_timezone(&Local)
+                .naive_local(),
+            None => local,
+        }
+    };
+    (day, midnight(day), midnight(day + chrono::Days::new(1)))
+}
+
+fn paused_until(config: &AppConfig, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+    let until = chrono::NaiveDate::parse_from_str(config.paused_until.as_deref()?, "%Y-%m-%d").ok()?;
+    (today < until).then_some(until)
+}
+
 fn start_scheduler() {
     let rt = Runtime::new().unwrap();
+    let schedules_path = get_schedules_path();
+    let mut schedules: Vec<ScheduleEntry> = vec![];
+    let mut schedules_modified = None;
+    let mut last_backup = None;
     loop {
         std::thread::sleep(std::time::Duration::from_secs(60));
         let config = load_config();
-        
+
+        // First backup shortly after startup, then one per day
+        let today = Local::now().date_naive();
+        if config.auto_backup && last_backup != Some(today) {
+            last_backup = Some(today);
+            match retry_if_busy(|| Ok(open_db(get_db_path())?)).and_then(|conn| backup_db(&conn, &get_backup_dir(&config), config.backup_keep)) {
+                Ok(path) => say!("💾 Backed up database to {}", path.display()),
+                Err(e) => say!("❌ Backup failed: {}", e),
+            }
+        }
+
+        // Pick up edits to schedules.toml without restarting; a broken edit keeps the previous entries
+        let modified = std::fs::metadata(&schedules_path).and_then(|m| m.modified()).ok();
+        if modified != schedules_modified {
+            schedules_modified = modified;
+            match load_schedules(&schedules_path) {
+                Ok(entries) => {
+                    say!("🔄 Loaded {} schedule(s) from {}", entries.len(), schedules_path.display());
+                    schedules = entries;
+                }
+                Err(e) => say!("❌ Ignoring {}: {}", schedules_path.display(), e),
+            }
+        }
+
         if !config.feishu_enabled { continue; }
-        if let Some(time_str) = config.schedule_time.clone() {
-            let now = Local::now().format("%H:%M").to_string();
-            if now == time_str {
-                println!("⏰ It's time! ({}) Starting scheduled report...", now);
-                // Trigger logic
-                rt.block_on(async {
-                    if let Err(e) = run_scheduled_job(config).await {
-                        println!("❌ Scheduled Job Failed: {}", e);
+        let now = Local::now();
+        // The next fire time is never earlier than the current minute, so it's due once it has passed
+        let due: Vec<(Option<&ScheduleEntry>, DateTime<chrono::FixedOffset>)> = if schedules.is_empty() {
+            let time = config.schedule_time.as_deref();
+            time.and_then(|t| next_fire_time(t, &config.schedule_weekdays, now)).filter(|t| *t <= now).map(|t| (None, t.fixed_offset())).into_iter().collect()
+        } else {
+            schedules.iter().filter_map(|e| Some((Some(e), e.next_fire(&config, now)?))).filter(|(_, t)| *t <= now).collect()
+        };
+        if due.is_empty() { continue; }
+        let mut state = load_scheduler_state();
+        for (entry, fire) in due {
+            let key = entry.map(|e| e.name.as_str()).unwrap_or(DEFAULT_SCHEDULE);
+            // The pause is by the day the report covers, which is in the schedule's own timezone
+            if let Some(until) = paused_until(&config, report_window(entry, now).0) {
+                say!("⏸️ Skipping scheduled report{}: paused until {}.", entry.map(|e| format!(" '{}'", e.name)).unwrap_or_default(), until);
+                continue;
+            }
+            let run = ScheduleRun { last_run_date: fire.format("%Y-%m-%d").to_string(), last_run_time: fire.format("%H:%M").to_string() };
+            // A daemon restarted within the scheduled minute must not run the same slot again
+            if state.get(key) == Some(&run) {
+                say!("⏭️ Schedule '{}' already ran at {} {}, skipping.", key, run.last_run_date, run.last_run_time);
+                continue;
+            }
+            state.insert(key.to_string(), run);
+            if let Err(e) = save_scheduler_state(&state) {
+                say!("⚠️ Failed to save scheduler state: {}", e);
+            }
+            let label = entry.map(|e| format!(" '{}'", e.name)).unwrap_or_default();
+            say!("⏰ It's time! ({}) Starting scheduled report{}...", now.format("%H:%M"), label);
+            // Trigger logic
+            rt.block_on(async {
+                let alert = match run_scheduled_job(config.clone(), entry).await {
+                    Ok(results) if results.iter().any(|r| !r.ok) => Some(format!("Report delivery incomplete: {}", format_sink_results(&results))),
+                    Ok(_) => None,
+                    // Nothing logged today isn't worth waking anyone up for
+                    Err(AppError::NotFound(msg)) => { say!("ℹ️ {}", msg); None },
+                    Err(e) => {
+                        say!("❌ Scheduled Job Failed: {}", e);
+                        Some(format!("Scheduled report failed: {}", e))
                     }
-                });
-                // Avoid double-running in the same minute
-                std::thread::sleep(std::time::Duration::from_secs(60)); 
+                };
+                if let (Some(text), true) = (alert, config.alert_on_failure) {
+                    send_failure_alert(&config, &text).await;
+                }
+            });
+        }
+        // Avoid double-running in the same minute
+        std::thread::sleep(std::time::Duration::from_secs(60));
+    }
+}
+
+// Delivery Sinks
+// Each Feishu recipient is its own sink, so one failed lookup doesn't block the others and
+// a retry only resends to the recipients that didn't get the report.
+#[derive(Debug, Clone, PartialEq)]
+enum Sink {
+    Feishu(String),
+}
+
+impl Sink {
+    fn name(&self) -> String {
+        match self {
+            Sink::Feishu(email) => format!("Feishu {}", email),
+        }
+    }
+
+    // Whether a `delivered_sinks` entry records this sink. Entries written before sinks were
+    // named per recipient just say "Feishu", which covered every Feishu target.
+    fn delivered_as(&self, entry: &str) -> bool {
+        match self {
+            Sink::Feishu(_) => entry == self.name() || entry == "Feishu",
+        }
+    }
+
+    // Sinks with enough configuration to attempt a delivery.
+    fn enabled(config: &AppConfig) -> Vec<Sink> {
+        let mut sinks = vec![];
+        if feishu_missing_fields(config).is_empty() {
+            sinks.extend(config.feishu_target_email.iter().map(|email| Sink::Feishu(email.clone())));
+        }
+        sinks
+    }
+
+    // `date` is the day the report covers, used to name attachments.
+    async fn deliver(&self, config: &AppConfig, text: &str, date: &str) -> AppResult<()> {
+        if offline() {
+            say!("📴 Offline mode: not sending to {}.", self.name());
+            return Ok(());
+        }
+        match self {
+            Sink::Feishu(target) => {
+                let (Some(app_id), Some(secret)) = (&config.feishu_app_id, &config.feishu_app_secret) else {
+                    return Err(AppError::Config("Feishu config missing".to_string()));
+                };
+                let client = FeishuClient::new(app_id.clone(), secret.clone(), http_client(config.user_agent.as_deref()));
+                let token = client.get_token().await?;
+                // The lookup is the flakiest step, so give transient network errors a couple more tries
+                let mut attempt = 1;
+                let user_id = loop {
+                    match client.get_user_id(&token, target).await {
+                        Err(AppError::Http(e)) if attempt < 3 => {
+                            say_err!("⚠️ Feishu lookup for {} failed ({}), retrying...", target, e);
+                            tokio::time::sleep(std::time::Duration::from_secs(2 * attempt)).await;
+                            attempt += 1;
+                        }
+                        result => break result?,
+                    }
+                };
+                if config.delivery_as_attachment {
+                    let file_name = format!("daily-report-{}.md", date);
+                    let file_key = client.upload_file(&token, &file_name, text.as_bytes().to_vec()).await?;
+                    return client.send_file(&token, &user_id, &file_key).await;
+                }
+                client.send_message(&token, &user_id, text).await
             }
         }
     }
 }
 
-async fn run_scheduled_job(config: AppConfig) -> Result<(), String> {
+// Required Feishu settings that are unset or empty.
+fn feishu_missing_fields(config: &AppConfig) -> Vec<&'static str> {
+    let mut missing = vec![];
+    if config.feishu_app_id.as_deref().is_none_or(str::is_empty) { missing.push("feishu_app_id"); }
+    if config.feishu_app_secret.as_deref().is_none_or(str::is_empty) { missing.push("feishu_app_secret"); }
+    if config.feishu_target_email.is_empty() { missing.push("feishu_target_email"); }
+    missing
+}
+
+// One sink kind as shown by `Config --sinks`.
+#[derive(Debug, Serialize)]
+pub struct SinkCheck {
+    kind: &'static str,
+    enabled: bool,
+    missing: Vec<&'static str>,
+    detail: String,
+}
+
+fn sink_checks(config: &AppConfig) -> Vec<SinkCheck> {
+    vec![SinkCheck {
+        kind: "Feishu",
+        enabled: config.feishu_enabled,
+        missing: feishu_missing_fields(config),
+        detail: format!("{} recipient(s): {}", config.feishu_target_email.len(), config.feishu_target_email.join(", ")),
+    }]
+}
+
+#[derive(Debug, Serialize)]
+pub struct SinkResult {
+    sink: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+// e.g. "Feishu: ok, Email: failed (timeout)"
+fn format_sink_results(results: &[SinkResult]) -> String {
+    if results.is_empty() {
+        return "no sinks to deliver to".to_string();
+    }
+    results.iter().map(|r| match &r.error {
+        None => format!("{}: ok", r.sink),
+        Some(e) => format!("{}: failed ({})", r.sink, e),
+    }).collect::<Vec<_>>().join(", ")
+}
+
+// The report followed by the expanded `delivery_footer`, the same for every sink. Lines left
+// empty by a blank placeholder (e.g. {link} without `report_link`) are dropped.
+fn with_delivery_footer(report: &str, config: &AppConfig, date: &str, commits: usize, logs: usize) -> String {
+    if config.delivery_footer.trim().is_empty() {
+        return report.to_string();
+    }
+    let link = config.report_link.as_deref().unwrap_or_default().replace("{date}", date);
+    let footer = config.delivery_footer.lines()
+        .map(|line| line.replace("{date}", date).replace("{commits}", &commits.to_string()).replace("{logs}", &logs.to_string()).replace("{link}", &link))
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{}\n\n{}", report.trim_end(), footer)
+}
+
+// `report` between the configured greeting and sign-off; either may be empty.
+fn with_greeting(report: &str, config: &AppConfig, date: &str) -> String {
+    let mut parts = vec![];
+    let prefix = config.report_prefix.replace("{date}", date);
+    if !prefix.trim().is_empty() { parts.push(prefix.trim_end().to_string()); }
+    parts.push(report.trim().to_string());
+    let suffix = config.report_suffix.replace("{date}", date);
+    if !suffix.trim().is_empty() { parts.push(suffix.trim().to_string()); }
+    parts.join("\n\n")
+}
+
+// Attempts every sink independently so one failing doesn't stop the others.
+async fn deliver_to_sinks(sinks: &[Sink], config: &AppConfig, text: &str, date: &str) -> Vec<SinkResult> {
+    let mut results = vec![];
+    for sink in sinks {
+        say!("🚀 Sending to {}...", sink.name());
+        let result = sink.deliver(config, text, date).await;
+        results.push(SinkResult { sink: sink.name(), ok: result.is_ok(), error: result.err().map(|e| e.to_string()) });
+    }
+    results
+}
+
+fn notify_desktop(title: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new().summary(title).body(body).show() {
+        say_err!("⚠️ Desktop notification failed: {}", e);
+    }
+}
+
+// Falls back to a desktop notification when no alert webhook is configured, or when it can't be reached.
+async fn send_failure_alert(config: &AppConfig, text: &str) {
+    if let Some(url) = config.alert_webhook_url.as_ref().filter(|_| !offline()) {
+        match http_client(config.user_agent.as_deref()).post(url).json(&serde_json::json!({ "text": text })).send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => return,
+            Err(e) => say_err!("⚠️ Alert webhook failed: {}", e),
+        }
+    }
+    notify_desktop("Daily Assistant", text);
+}
+
+// Pushes hand-written or hand-edited text through the enabled sinks, skipping the AI.
+#[tauri::command]
+async fn send_text(text: String) -> AppResult<Vec<SinkResult>> {
+    let config = load_config();
+    let sinks = Sink::enabled(&config);
+    if sinks.is_empty() {
+        return Err(AppError::Config("No delivery sinks configured".to_string()));
+    }
+    if text.trim().is_empty() {
+        return Err(AppError::Config("Nothing to send".to_string()));
+    }
+    Ok(deliver_to_sinks(&sinks, &config, &text, &Local::now().format("%Y-%m-%d").to_string()).await)
+}
+
+// Entries from schedules.toml store their report under "DATE#NAME", so several reports a day
+// don't collide; the default schedule uses the bare date.
+async fn run_scheduled_job(config: AppConfig, schedule: Option<&ScheduleEntry>) -> AppResult<Vec<SinkResult>> {
     // 1. Collect Data
     // We need DB access. Since we are in a thread, we can try to use app.state().
     // But rusqlite usage in threads is tricky if not careful.
@@ -712,45 +4792,303 @@ async fn run_scheduled_job(config: AppConfig) -> Result<(), String> {
     // Let's re-use the logic from Review command but purely in Rust.
     
     let db_path = get_db_path();
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-    
-    // 1. Logs
-    let mut logs = vec![];
-    let mut stmt = conn.prepare("SELECT id, content, log_type, timestamp FROM logs WHERE date(timestamp) = date('now', 'localtime') ORDER BY id DESC").map_err(|e| e.to_string())?;
-    let iter = stmt.query_map([], |row| Ok(LogItem { id: row.get(0)?, content: row.get(1)?, log_type: row.get(2)?, timestamp: row.get(3)? })).map_err(|e| e.to_string())?;
-    for l in iter { logs.push(l.unwrap()); }
+    let conn = retry_if_busy(|| Ok(open_db(db_path.clone())?))?;
+    let config = schedule.map(|e| e.apply(&config)).unwrap_or(config);
+    let mode = schedule.map(|e| e.mode()).unwrap_or("analysis");
+    let (day, start, end) = report_window(schedule, Local::now());
+    let date = day.format("%Y-%m-%d").to_string();
+    let since = format!("--since={}", start.format("%Y-%m-%d %H:%M:%S"));
+    let until = format!("--until={}", (end - chrono::Duration::seconds(1)).format("%Y-%m-%d %H:%M:%S"));
+    let today = match schedule {
+        Some(e) => format!("{}#{}", date, e.name),
+        None => date.clone(),
+    };
+
+    // A stored report means an earlier run already got past the AI step today.
+    // (log count, commit count) for the footer; a reused report has to count again
+    let (report, counts) = match retry_if_busy(|| load_report(&conn, &today))? {
+        Some(stored) if stored.delivered => {
+            say!("✅ Today's report was already delivered, skipping.");
+            return Ok(vec![]);
+        }
+        Some(stored) => {
+            say!("♻️ Reusing today's stored report.");
+            (stored.content, None)
+        }
+        None => {
+            // 1. Logs
+            let logs = retry_if_busy(|| query_logs_in_window(&conn, start, end))?;
 
-    // 2. Commits
-    let commits = scan_git_repos(config.git_paths.clone(), config.deep_analysis).unwrap_or_default();
+            // 2. Commits
+            let commits = scan_git_window(config.git_paths.clone(), config.deep_analysis, &since, Some(&until)).unwrap_or_default();
 
-    if logs.is_empty() && commits.is_empty() {
-        return Err("No logs or commits today. Skipping report.".to_string());
-    }
+            if logs.is_empty() && commits.is_empty() {
+                return Err(AppError::NotFound("No logs or commits today. Skipping report.".to_string()));
+            }
+            let min = config.min_activity_threshold;
+            if logs.len() < min && commits.len() < min {
+                say!("💤 Light day ({} logs, {} commits; threshold {}). Skipping report.", logs.len(), commits.len(), min);
+                return Ok(vec![]);
+            }
 
-    // 3. Prompt
-    let prompt = generate_prompt_logic(&logs, &commits, &config, "analysis");
+            // 3. Prompt
+            let prompt = build_review_prompt(&logs, &commits, &config, mode).await?;
 
-    // 4. AI
-    let req = AiRequest {
-        provider: config.provider.clone(),
-        api_key: config.api_key.clone(),
-        model: config.model.clone(),
-        base_url: config.base_url.clone(),
-        prompt,
+            // 4. AI
+            let report = render_ai_reply(call_ai(build_ai_request(&config, prompt, mode)).await?, &config, mode)?;
+            // A stub reply must not be reused as today's report once back online
+            if !offline() {
+                store_report(&conn, &today, mode, &report)?;
+                store_report_diffs(&conn, &today, &commits)?;
+            }
+            (report, Some((logs.len(), commits.len())))
+        }
     };
-    let report = call_ai(req).await?;
-
-    // 5. Send to Feishu
-    if let (Some(app_id), Some(secret), Some(target)) = (config.feishu_app_id, config.feishu_app_secret, config.feishu_target_email) {
-        println!("🚀 Sending to Feishu...");
-        let client = FeishuClient::new(app_id, secret);
-        let token = client.get_token().await?;
-        let user_id = client.get_user_id(&token, &target).await?;
-        client.send_message(&token, &user_id, &report).await?;
-        println!("✅ Feishu Message Sent!");
-    } else {
-        println!("⚠️ Feishu config missing, skipping send.");
+
+    // 5. Deliver, skipping sinks that already received today's report on an earlier attempt
+    let already_sent = retry_if_busy(|| delivered_sinks(&conn, &today))?;
+    let pending = pending_sinks(Sink::enabled(&config), &already_sent);
+    if pending.is_empty() && already_sent.is_empty() {
+        say!("⚠️ No delivery sinks configured, skipping send.");
+        if config.notify_on_success {
+            notify_desktop("Daily Assistant", "Today's report is ready.");
+        }
+        return Ok(vec![]);
+    }
+
+    let (logs, commits) = match counts {
+        Some(counts) => counts,
+        None if config.delivery_footer.trim().is_empty() => (0, 0),
+        None => (
+            retry_if_busy(|| query_logs_in_window(&conn, start, end))?.len(),
+            scan_git_window(config.git_paths.clone(), false, &since, Some(&until)).map(|c| c.len()).unwrap_or_default(),
+        ),
+    };
+    let report = with_delivery_footer(&with_greeting(&report, &config, &date), &config, &date, commits, logs);
+    let results = deliver_to_sinks(&pending, &config, &report, &date).await;
+    if offline() {
+        say!("📴 Offline mode: delivery not recorded.");
+        return Ok(results);
+    }
+    if record_delivery(&conn, &today, &results)? {
+        if config.notify_on_success {
+            notify_desktop("Daily Assistant", &format!("Today's report was sent ({}).", format_sink_results(&results)));
+        }
     }
+    say!("📬 Delivery: {}", format_sink_results(&results));
     
-    Ok(())
+    Ok(results)
+}
+
+// `sinks` minus those that already received the report, per its `delivered_sinks` entries.
+fn pending_sinks(sinks: Vec<Sink>, already_sent: &[String]) -> Vec<Sink> {
+    sinks.into_iter().filter(|s| !already_sent.iter().any(|d| s.delivered_as(d))).collect()
+}
+
+// Records the sinks that got the report, and the report itself once every sink in this attempt
+// succeeded. Returns whether they all did.
+fn record_delivery(conn: &Connection, date: &str, results: &[SinkResult]) -> AppResult<bool> {
+    for r in results.iter().filter(|r| r.ok) {
+        mark_sink_delivered(conn, date, &r.sink)?;
+    }
+    let all_ok = results.iter().all(|r| r.ok);
+    if all_ok {
+        mark_report_delivered(conn, date)?;
+    }
+    Ok(all_ok)
+}
+
+// Heads the report delivered by a test run, so recipients can't mistake it for a real one.
+const TEST_RUN_BANNER: &str = "🧪 TEST RUN — generated from sample data, not a real report.";
+
+// The scheduled pipeline on made-up input, for checking the AI and sinks on a quiet day.
+// Nothing touches the DB, and the delivered report is marked as a test.
+async fn run_test_pipeline(config: AppConfig) -> AppResult<(String, Vec<SinkResult>)> {
+    let now = Local::now();
+    let timestamp = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let logs: Vec<LogItem> = [
+        ("Set up Daily Assistant and checked the scheduled report", "task", true),
+        ("Reviewed the delivery settings with the team", "note", false),
+        ("Report layout still needs a decision", "problem", false),
+    ]
+    .into_iter()
+    .map(|(content, log_type, completed)| LogItem {
+        id: None,
+        content: content.to_string(),
+        log_type: log_type.to_string(),
+        timestamp: timestamp.clone(),
+        completed,
+    })
+    .collect();
+    let commits = vec![GitCommit {
+        hash: "0000000000000000000000000000000000000000".to_string(),
+        message: "Add sample report configuration".to_string(),
+        author: config.git_author.clone().unwrap_or_else(|| "Test Run".to_string()),
+        time: now.timestamp(),
+        repo_name: Some("sample-repo".to_string()),
+        diff: None,
+        body: None,
+        omitted: 0,
+        branch: None,
+        full_diff: None,
+    }];
+
+    let prompt = build_review_prompt(&logs, &commits, &config, "analysis").await?;
+    let report = render_ai_reply(call_ai(build_ai_request(&config, prompt, "analysis")).await?, &config, "analysis")?;
+    let date = now.format("%Y-%m-%d").to_string();
+    let report = format!(
+        "{}\n\n{}",
+        TEST_RUN_BANNER,
+        with_delivery_footer(&with_greeting(&report, &config, &date), &config, &date, commits.len(), logs.len())
+    );
+
+    let sinks = Sink::enabled(&config);
+    if sinks.is_empty() {
+        return Ok((report, vec![]));
+    }
+    let results = deliver_to_sinks(&sinks, &config, &report, &date).await;
+    Ok((report, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty directory under the system temp dir, unique to the test and this run.
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("da-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sink_result(sink: &Sink, ok: bool) -> SinkResult {
+        SinkResult { sink: sink.name(), ok, error: (!ok).then(|| "timed out".to_string()) }
+    }
+
+    #[test]
+    fn retry_resends_only_to_failed_sinks() {
+        let conn = open_db(temp_dir("retry").join("test.db")).unwrap();
+        let date = "2026-10-17";
+        store_report(&conn, date, "analysis", "report").unwrap();
+        let sinks = || vec![Sink::Feishu("a@x.com".to_string()), Sink::Feishu("b@x.com".to_string())];
+
+        // First attempt: a gets the report, b fails
+        let first = sinks();
+        let results = [sink_result(&first[0], true), sink_result(&first[1], false)];
+        assert!(!record_delivery(&conn, date, &results).unwrap());
+        assert!(!load_report(&conn, date).unwrap().unwrap().delivered);
+
+        // The retry goes to b only
+        let pending = pending_sinks(sinks(), &delivered_sinks(&conn, date).unwrap());
+        assert_eq!(pending.iter().map(Sink::name).collect::<Vec<_>>(), ["Feishu b@x.com"]);
+        assert!(record_delivery(&conn, date, &[sink_result(&pending[0], true)]).unwrap());
+        assert!(load_report(&conn, date).unwrap().unwrap().delivered);
+
+        // and nothing is left to send
+        assert!(pending_sinks(sinks(), &delivered_sinks(&conn, date).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn parse_git_log_keeps_pipes_in_subjects() {
+        let output = "abc123\x1fAlice\x1f1700000000\x1frefs/heads/main\x1ffix: handle a|b case\x1e\n";
+        let commits = parse_git_log(output);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].hash, "abc123");
+        assert_eq!(commits[0].author, "Alice");
+        assert_eq!(commits[0].time, 1700000000);
+        assert_eq!(commits[0].branch.as_deref(), Some("main"));
+        assert_eq!(commits[0].message, "fix: handle a|b case");
+        assert_eq!(commits[0].body, None);
+    }
+
+    #[test]
+    fn parse_git_log_reads_multi_line_bodies() {
+        let output = concat!(
+            "abc123\x1fAlice\x1f1700000000\x1fHEAD\x1fAdd retries\x1fFirst line.\n\nSecond | paragraph.\n\x1e\n",
+            "def456\x1fBob\x1f1700000100\x1fHEAD\x1fBump version\x1f\x1e\n",
+        );
+        let commits = parse_git_log(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].message, "Add retries");
+        assert_eq!(commits[0].body.as_deref(), Some("First line.\n\nSecond | paragraph."));
+        assert_eq!(commits[0].branch, None);
+        assert_eq!(commits[1].hash, "def456");
+        assert_eq!(commits[1].body, None);
+    }
+
+    // Scans read config.json from the user's config dir; point that at an empty directory so
+    // they run with the defaults.
+    fn use_default_config() {
+        static ONCE: std::sync::Once = std::sync::Once::new();
+        ONCE.call_once(|| {
+            let home = temp_dir("home");
+            std::env::set_var("HOME", &home);
+            std::env::set_var("APPDATA", &home);
+        });
+    }
+
+    fn git(repo: &std::path::Path, args: &[&str]) {
+        let status = Command::new("git").arg("-C").arg(repo).args(args).status().unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    // A new repo with its own identity, so author detection picks up its commits.
+    fn init_repo(name: &str) -> String {
+        let dir = temp_dir(name);
+        git(&dir, &["init", "-q"]);
+        git(&dir, &["config", "user.email", "test@example.com"]);
+        git(&dir, &["config", "user.name", "Test"]);
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn scanning_a_repo_without_commits_finds_nothing() {
+        use_default_config();
+        let repo = init_repo("empty-repo");
+        assert!(!repo_has_commits(&repo));
+        let commits = scan_git_window(vec![repo], false, "--since=2000-01-01", None).unwrap();
+        assert!(commits.is_empty());
+    }
+
+    #[test]
+    fn scanning_a_repo_with_one_commit_finds_it() {
+        use_default_config();
+        let repo = init_repo("one-commit-repo");
+        git(std::path::Path::new(&repo), &["-c", "commit.gpgsign=false", "commit", "-q", "--allow-empty", "-m", "First commit"]);
+        assert!(repo_has_commits(&repo));
+        let commits = scan_git_window(vec![repo], false, "--since=2000-01-01", None).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].message, "First commit");
+        assert_eq!(commits[0].author, "Test");
+    }
+
+    #[test]
+    fn format_timestamp_falls_back_to_the_raw_string() {
+        assert_eq!(format_timestamp("2026-10-17 09:05:00", "%H:%M"), "09:05");
+        for raw in ["", "9:05", "not a time", "2026-13-45 99:99:99"] {
+            assert_eq!(format_timestamp(raw, "%H:%M"), raw);
+        }
+    }
+
+    #[test]
+    fn write_waits_out_another_connections_lock() {
+        let path = temp_dir("busy").join("test.db");
+        let writer = open_db(path.clone()).unwrap();
+        let other = open_db(path).unwrap();
+        // Shorter than the lock is held, so the first attempt fails with SQLITE_BUSY and is retried
+        other.busy_timeout(std::time::Duration::from_millis(50)).unwrap();
+
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        insert_log(&writer, "first", "note").unwrap();
+        let holder = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(700));
+            writer.execute_batch("COMMIT").unwrap();
+        });
+
+        retry_if_busy(|| insert_log(&other, "second", "note")).unwrap();
+        holder.join().unwrap();
+        let count: i64 = other.query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
+    }
 }
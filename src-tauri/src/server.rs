@@ -0,0 +1,131 @@
+// Local HTTP API (`da serve`)
+// Exposes the same operations as the CLI so editors and scripts can drive
+// the assistant without the GUI.
+use std::sync::Arc;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    generate_review, get_db_path, insert_log, limit_log_length, load_config, query_today_logs, run_scheduled_job, scan_git_repos,
+    AppConfig, AppError, AppResult, DbState, GitCommit, LogItem,
+};
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Config(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Http(_) => StatusCode::BAD_GATEWAY,
+            AppError::Db(_) | AppError::Git(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct AddLogBody {
+    content: String,
+    log_type: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SyncBody {
+    deep: bool,
+    until: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReviewBody {
+    export: bool,
+}
+
+fn lock(db: &DbState) -> AppResult<std::sync::MutexGuard<'_, rusqlite::Connection>> {
+    db.conn.lock().map_err(|_| AppError::Db("Failed to lock db".to_string()))
+}
+
+async fn list_logs(State(db): State<Arc<DbState>>) -> AppResult<Json<Vec<LogItem>>> {
+    let conn = lock(&db)?;
+    Ok(Json(query_today_logs(&conn)?))
+}
+
+async fn add_log(State(db): State<Arc<DbState>>, Json(body): Json<AddLogBody>) -> AppResult<Json<serde_json::Value>> {
+    let (content, truncated) = limit_log_length(&body.content, &load_config());
+    let conn = lock(&db)?;
+    insert_log(&conn, &content, body.log_type.as_deref().unwrap_or("note"))?;
+    Ok(Json(match truncated {
+        Some(len) => serde_json::json!({ "message": "Log saved successfully", "warning": format!("Content truncated ({} characters was over the limit)", len) }),
+        None => serde_json::json!({ "message": "Log saved successfully" }),
+    }))
+}
+
+async fn sync(body: Option<Json<SyncBody>>) -> AppResult<Json<Vec<GitCommit>>> {
+    let config = load_config();
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    Ok(Json(scan_git_repos(config.git_paths, body.deep || config.deep_analysis, body.until)?))
+}
+
+// Requires `Authorization: Bearer <webhook_token>`, since a review runs the configured
+// `context_commands` and a paid AI call (and a body-less POST is something any web page can send).
+async fn review(State(db): State<Arc<DbState>>, headers: HeaderMap, body: Option<Json<ReviewBody>>) -> AppResult<Json<serde_json::Value>> {
+    let config = load_config();
+    check_webhook_token(&config, &headers)?;
+    let export = body.map(|Json(b)| b.export).unwrap_or_default();
+    let logs = query_today_logs(&*lock(&db)?)?;
+    // The git scan and context commands block (up to 10s per command), so keep them off the async workers
+    let report = tokio::task::spawn_blocking(move || {
+        tokio::runtime::Handle::current().block_on(generate_review(&logs, &config, if export { "export" } else { "analysis" }, None, None))
+    })
+    .await
+    .map_err(|e| AppError::Config(format!("Review task failed: {}", e)))??;
+    Ok(Json(serde_json::json!({ "report": report })))
+}
+
+fn check_webhook_token(config: &AppConfig, headers: &HeaderMap) -> AppResult<()> {
+    let Some(expected) = config.webhook_token.as_deref().filter(|t| !t.is_empty()) else {
+        return Err(AppError::Unauthorized("Webhook token is not configured".to_string()));
+    };
+    let provided = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(expected) {
+        return Err(AppError::Unauthorized("Invalid webhook token".to_string()));
+    }
+    Ok(())
+}
+
+// Lets CI, cron or other automation kick off the scheduled pipeline
+// (review + delivery). Requires `Authorization: Bearer <webhook_token>`.
+async fn trigger_review(headers: HeaderMap) -> AppResult<Json<serde_json::Value>> {
+    let config = load_config();
+    check_webhook_token(&config, &headers)?;
+    let deliveries = run_scheduled_job(config, None).await?;
+    Ok(Json(serde_json::json!({ "message": "Review triggered successfully", "deliveries": deliveries })))
+}
+
+pub async fn serve(host: &str, port: u16) -> AppResult<()> {
+    let db = DbState::init(get_db_path())?;
+    let app = Router::new()
+        .route("/logs", get(list_logs).post(add_log))
+        .route("/sync", post(sync))
+        .route("/review", post(review))
+        .route("/trigger-review", post(trigger_review))
+        .with_state(Arc::new(db));
+
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .map_err(|e| AppError::Config(format!("Failed to bind {}:{}: {}", host, port, e)))?;
+    say!("🌐 Serving on http://{}:{}", host, port);
+    axum::serve(listener, app)
+        .await
+        .map_err(|e| AppError::Http(e.to_string()))
+}
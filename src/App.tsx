@@ -8,6 +8,7 @@ interface LogItem {
   content: string;
   log_type: string;
   timestamp: string;
+  completed?: boolean;
 }
 
 interface GitCommit {
@@ -19,6 +20,15 @@ interface GitCommit {
   diff?: string;
 }
 
+// Backend Error Shape (see AppError in lib.rs)
+interface AppError {
+    kind: 'db' | 'http' | 'config' | 'git' | 'not_found' | 'unauthorized';
+    message: string;
+}
+
+const errorMessage = (e: unknown) =>
+    typeof e === 'object' && e !== null && 'message' in e ? (e as AppError).message : String(e);
+
 // Backend Config Interface
 interface AppConfig {
     api_key: string;
@@ -29,8 +39,16 @@ interface AppConfig {
     custom_rules: string;
     report_template: string;
     deep_analysis: boolean;
+    user_agent?: string | null;
 }
 
+// Mirrors `provider_preset` in the backend
+const PROVIDER_PRESETS: Record<string, Pick<AppConfig, 'base_url' | 'model'>> = {
+    openai: { base_url: 'https://api.openai.com/v1', model: 'gpt-4o' },
+    deepseek: { base_url: 'https://api.deepseek.com/v1', model: 'deepseek-chat' },
+    moonshot: { base_url: 'https://api.moonshot.cn/v1', model: 'moonshot-v1-8k' },
+};
+
 function App() {
   const [activeTab, setActiveTab] = useState<'log' | 'review' | 'settings'>('log');
   const [logs, setLogs] = useState<LogItem[]>([]);
@@ -84,7 +102,7 @@ function App() {
         await invoke('save_config', { config });
         alert('Settings Saved Successfully to Backend!');
     } catch (e) {
-        alert('Failed to save settings: ' + e);
+        alert('Failed to save settings: ' + errorMessage(e));
     }
   };
 
@@ -92,6 +110,12 @@ function App() {
       setConfig(prev => ({ ...prev, [key]: value }));
   };
 
+  // Selecting a preset provider fills in its base URL and default model
+  const selectProvider = (provider: string) => {
+      const preset = PROVIDER_PRESETS[provider];
+      setConfig(prev => preset ? { ...prev, provider, ...preset } : { ...prev, provider });
+  };
+
   const addGitPath = () => {
       if(newGitPath && !config.git_paths.includes(newGitPath)) {
           updateConfig('git_paths', [...config.git_paths, newGitPath]);
@@ -110,7 +134,7 @@ function App() {
       setNewLog('');
       await loadLogs(); 
     } catch (e) {
-      alert('Failed to save log: ' + e);
+      alert('Failed to save log: ' + errorMessage(e));
     }
   };
   
@@ -120,7 +144,7 @@ function App() {
           await invoke('delete_log', { id });
           await loadLogs(); 
       } catch (e) {
-          alert('Failed to delete log: ' + e);
+          alert('Failed to delete log: ' + errorMessage(e));
       }
   };
 
@@ -140,7 +164,7 @@ function App() {
           alert(`Synced ${commits.length} commits with deep analysis.`);
       }
     } catch (e) {
-      alert('Git Sync Failed: ' + e);
+      alert('Git Sync Failed: ' + errorMessage(e));
     }
   };
 
@@ -210,12 +234,13 @@ function App() {
           api_key: config.api_key,
           model: config.model,
           prompt: prompt,
-          base_url: config.base_url 
+          base_url: config.base_url,
+          user_agent: config.user_agent
         }
       });
       setReviewResult(response);
     } catch (e) {
-      alert('AI Action Failed: ' + e);
+      alert('AI Action Failed: ' + errorMessage(e));
     } finally {
       setIsAiLoading(false);
     }
@@ -386,8 +411,10 @@ function App() {
                    
                    <div className="mb-4">
                      <label>AI Provider</label>
-                     <select value={config.provider} onChange={(e) => updateConfig('provider', e.target.value)}>
+                     <select value={config.provider} onChange={(e) => selectProvider(e.target.value)}>
                        <option value="openai">OpenAI / Compatible</option>
+                       <option value="deepseek">DeepSeek</option>
+                       <option value="moonshot">Moonshot (Kimi)</option>
                        <option value="anthropic">Anthropic</option>
                        <option value="gemini">Gemini</option>
                      </select>